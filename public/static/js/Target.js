@@ -10,13 +10,21 @@ define(["knockout", "moment"], function(ko, moment) {
 		this.endTime = params.endTime;
 		this.threadName = params.threadName;
 		this.groupName = params.groupName;
+		this.running = params.running === true;
+		this.critical = params.critical === true;
+		this.kind = params.kind || "task";
+		this.depth = params.depth || 0;
 
 		this.startTimeText = ko.pureComputed(function() {
 			return moment(this.startTime).format(TIME_FROMAT);
 		}, this);
 
 		this.endTimeText = ko.pureComputed(function() {
-			return moment(this.endTime).format(TIME_FROMAT);
+			if (this.running) {
+				return moment(this.endTime).format(TIME_FROMAT) + " (running)";
+			} else {
+				return moment(this.endTime).format(TIME_FROMAT);
+			}
 		}, this);
 
 		this.durationText = ko.pureComputed(function() {
@@ -17,6 +17,16 @@ define(["knockout", "d3", "moment", "Util", "d3-color", "d3-axis"], function(ko,
 	const CHART_CURSOR_COLOR = "#ffffff";
 	const CHART_PRIMARY_COLOR = "#000000";
 	const CHART_SECONDARY_COLOR = "#00000040";
+	const CHART_RUNNING_ALPHA = 0.5;
+	const CHART_RUNNING_DASH = [4, 3];
+	const CHART_CRITICAL_COLOR = "#db2828";
+	const CHART_CRITICAL_WIDTH = 2;
+	const CHART_BLOCK_HEIGHT = 21;
+	const CHART_NESTED_INSET = 3;
+	const CHART_NESTED_MIN_HEIGHT = 3;
+	const CHART_MARKER_DASH = [6, 4];
+	const CHART_MARKER_TEXT_OFFSET_X = 4;
+	const CHART_MARKER_TEXT_OFFSET_Y = 14;
 	const CHART_TEXT_OFFSET_X = 8;
 	const CHART_TEXT_PADDING_LEFT = 8;
 	const CHART_TEXT_OFFSET_Y = 23;
@@ -236,8 +246,10 @@ define(["knockout", "d3", "moment", "Util", "d3-color", "d3-axis"], function(ko,
 		let timeMax = -Infinity;
 
 		for (const item of items) {
-			uniqueThreads.add(item.threadName);
-			uniqueGroups.add(item.groupName);
+			if (item.kind !== "marker") {
+				uniqueThreads.add(item.threadName);
+				uniqueGroups.add(item.groupName);
+			}
 
 			timeMin = Math.min(timeMin, item.startTime);
 			timeMax = Math.max(timeMax, item.endTime);
@@ -305,6 +317,7 @@ define(["knockout", "d3", "moment", "Util", "d3-color", "d3-axis"], function(ko,
 		this.drawYAxis();
 		this.drawXAxis();
 		this.drawBlocks();
+		this.drawMarkers();
 		this.drawCursor();
 	};
 
@@ -344,8 +357,15 @@ define(["knockout", "d3", "moment", "Util", "d3-color", "d3-axis"], function(ko,
 		this.context.lineWidth = 1;
 		this.context.strokeStyle = "#00000060";
 
+		const labels = [];
+
 		for (const item of this.items) {
-			if (item.endTime < this.viewMin || item.startTime > this.viewMax) {
+			if (item.endTime < this.viewMin || item.startTime > this.viewMax || item.kind === "marker") {
+				continue;
+			}
+
+			if (item.kind === "milestone") {
+				this.drawMilestone(item);
 				continue;
 			}
 
@@ -368,58 +388,139 @@ define(["knockout", "d3", "moment", "Util", "d3-color", "d3-axis"], function(ko,
 
 			const threadIndex = this.threadLines[item.threadName];
 			const groupColor = this.groupColors[item.groupName];
+			// Nested targets are drawn inside their parents, every level is a bit lower.
+			const inset = Math.min(CHART_NESTED_INSET * item.depth, (CHART_BLOCK_HEIGHT - CHART_NESTED_MIN_HEIGHT) / 2);
 
 			this.context.beginPath();
-			this.context.rect(startX, CHART_THREAD_OFFSET + CHART_THREAD_HEIGHT * threadIndex, width, 21);
+			this.context.rect(
+				startX,
+				CHART_THREAD_OFFSET + CHART_THREAD_HEIGHT * threadIndex + inset,
+				width,
+				CHART_BLOCK_HEIGHT - 2 * inset
+			);
 			this.context.fillStyle = groupColor;
 
+			if (item.running) {
+				this.context.globalAlpha = CHART_RUNNING_ALPHA;
+				this.context.setLineDash(CHART_RUNNING_DASH);
+			}
+
 			if (width > THRESHOLD_LINE) {
 				this.context.fill();
 			}
 
-			this.context.stroke();
+			this.context.globalAlpha = 1.0;
 
-			if (width > THRESHOLD_TEXT) {
-				this.context.font = CHART_FONT;
-				this.context.fillStyle = "#000000";
-				this.context.textAlign = "left";
-
-				const name = item.name;
-				let blockWidth = width - CHART_TEXT_OFFSET_X - CHART_TEXT_PADDING_LEFT;
-				let textWidth = this.context.measureText(name).width;
-
-				if (textWidth < blockWidth) {
-					this.context.fillText(
-						name,
-						CHART_TEXT_OFFSET_X + startX,
-						CHART_TEXT_OFFSET_Y + CHART_THREAD_HEIGHT * threadIndex
-					);
-				} else {
-					let trimIndex = Math.ceil((blockWidth / textWidth) * name.length);
-					let nameText = name.substring(0, trimIndex) + HELLIP;
+			if (item.critical) {
+				this.context.save();
+				this.context.lineWidth = CHART_CRITICAL_WIDTH;
+				this.context.strokeStyle = CHART_CRITICAL_COLOR;
+				this.context.stroke();
+				this.context.restore();
+			} else {
+				this.context.stroke();
+			}
 
-					textWidth = this.context.measureText(nameText).width;
+			this.context.setLineDash([]);
 
-					if (textWidth > blockWidth) {
-						do {
-							trimIndex -= 1;
-							nameText = name.substring(0, trimIndex) + HELLIP;
-							textWidth = this.context.measureText(nameText).width;
-						} while (textWidth > blockWidth);
-					}
-
-					this.context.fillText(
-						nameText,
-						CHART_TEXT_OFFSET_X + startX,
-						CHART_TEXT_OFFSET_Y + CHART_THREAD_HEIGHT * threadIndex
-					);
-				}
+			// Labels of nested targets are not drawn, so they do not cover labels of their parents.
+			if (item.depth === 0 && width > THRESHOLD_TEXT) {
+				labels.push({ item: item, startX: startX, width: width, threadIndex: threadIndex });
 			}
 		}
 
+		for (const label of labels) {
+			this.drawLabel(label.item.name, label.startX, label.width, label.threadIndex);
+		}
+
 		this.context.restore();
 	};
 
+	GanttChart.prototype.drawLabel = function(name, startX, width, threadIndex) {
+		this.context.font = CHART_FONT;
+		this.context.fillStyle = "#000000";
+		this.context.textAlign = "left";
+
+		let blockWidth = width - CHART_TEXT_OFFSET_X - CHART_TEXT_PADDING_LEFT;
+		let textWidth = this.context.measureText(name).width;
+
+		if (textWidth < blockWidth) {
+			this.context.fillText(
+				name,
+				CHART_TEXT_OFFSET_X + startX,
+				CHART_TEXT_OFFSET_Y + CHART_THREAD_HEIGHT * threadIndex
+			);
+		} else {
+			let trimIndex = Math.ceil((blockWidth / textWidth) * name.length);
+			let nameText = name.substring(0, trimIndex) + HELLIP;
+
+			textWidth = this.context.measureText(nameText).width;
+
+			if (textWidth > blockWidth) {
+				do {
+					trimIndex -= 1;
+					nameText = name.substring(0, trimIndex) + HELLIP;
+					textWidth = this.context.measureText(nameText).width;
+				} while (textWidth > blockWidth);
+			}
+
+			this.context.fillText(
+				nameText,
+				CHART_TEXT_OFFSET_X + startX,
+				CHART_TEXT_OFFSET_Y + CHART_THREAD_HEIGHT * threadIndex
+			);
+		}
+	};
+
+	GanttChart.prototype.drawMilestone = function(item) {
+		const x = this.xScale(item.startTime);
+		const half = CHART_BLOCK_HEIGHT / 2;
+		const y = CHART_THREAD_OFFSET + CHART_THREAD_HEIGHT * this.threadLines[item.threadName] + half;
+
+		this.context.beginPath();
+		this.context.moveTo(x, y - half);
+		this.context.lineTo(x + half, y);
+		this.context.lineTo(x, y + half);
+		this.context.lineTo(x - half, y);
+		this.context.closePath();
+		this.context.fillStyle = this.groupColors[item.groupName];
+		this.context.fill();
+
+		if (item.critical) {
+			this.context.save();
+			this.context.lineWidth = CHART_CRITICAL_WIDTH;
+			this.context.strokeStyle = CHART_CRITICAL_COLOR;
+			this.context.stroke();
+			this.context.restore();
+		} else {
+			this.context.stroke();
+		}
+	};
+
+	GanttChart.prototype.drawMarkers = function() {
+		for (const item of this.items) {
+			if (item.kind !== "marker" || item.startTime < this.viewMin || item.startTime > this.viewMax) {
+				continue;
+			}
+
+			const x = this.xScale(item.startTime);
+
+			this.context.lineWidth = 1;
+			this.context.strokeStyle = CHART_PRIMARY_COLOR;
+			this.context.setLineDash(CHART_MARKER_DASH);
+			this.context.beginPath();
+			this.context.moveTo(x, CHART_PADDING_TOP);
+			this.context.lineTo(x, CHART_PADDING_TOP + CHART_THREAD_HEIGHT * this.nThreadLines);
+			this.context.stroke();
+			this.context.setLineDash([]);
+
+			this.context.font = CHART_FONT;
+			this.context.fillStyle = "#000000";
+			this.context.textAlign = "left";
+			this.context.fillText(item.name, x + CHART_MARKER_TEXT_OFFSET_X, CHART_PADDING_TOP + CHART_MARKER_TEXT_OFFSET_Y);
+		}
+	};
+
 	GanttChart.prototype.drawYAxis = function() {
 		const scaleRange = this.yScale.range();
 
@@ -40,8 +40,13 @@ define(["knockout", "moment", "Target", "Util", "bindings/GanttChart"], function
 		let totalTime = 0;
 
 		for (const row of rows) {
+			if (row.kind === "marker") {
+				continue;
+			}
+
 			const name = row.groupName;
-			const duration = row.endTime - row.startTime;
+			// Nested targets are covered by their top-level target, so only top-level time is counted.
+			const duration = row.depth > 0 ? 0 : row.endTime - row.startTime;
 
 			if (name in groupStatistics) {
 				groupStatistics[name] += duration;
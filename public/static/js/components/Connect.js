@@ -4,6 +4,7 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 	const STATE_READY = "Ready";
 	const STATE_LOADING = "Loading";
 	const STATE_ERROR = "Error";
+	const STAGE_PAGE_SIZE = 100;
 
 	const getQueryParams = function() {
 		const query = document.location.search.replace(/^\?/, "");
@@ -24,9 +25,22 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 		return queryParams;
 	};
 
+	const errorMessage = function(resp) {
+		try {
+			const body = JSON.parse(resp.responseText);
+
+			return body.message || resp.responseText;
+		} catch (e) {
+			return resp.responseText;
+		}
+	};
+
 	const Connect = function(params) {
 		this.callback = params.callback;
 
+		this.profileList = ko.observableArray([]);
+		this.profileSelected = ko.observable();
+		this.allowCredentials = ko.observable(false);
 		this.serverName = ko.observable("");
 		this.portNumber = ko.observable(5432);
 		this.userName = ko.observable("");
@@ -35,22 +49,33 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 		this.databaseSelected = ko.observable();
 		this.stageList = ko.observableArray([]);
 		this.stageSelected = ko.observable();
+		this.stageFilter = ko.observable("");
+		this.stageTotal = ko.observable(0);
+		this.criticalPath = ko.observable(false);
 		this.errorMessage = ko.observable("");
 
 		this.state = ko.observable(STATE_READY);
 
+		this.isProfileSelected = ko.pureComputed(function() {
+			return this.profileSelected() !== undefined;
+		}, this);
+
+		this.isCredentialsVisible = ko.pureComputed(function() {
+			return this.allowCredentials() && !this.isProfileSelected();
+		}, this);
+
 		this.isServerNameInvalid = ko.pureComputed(function() {
-			return this.serverName().length === 0;
+			return !this.isProfileSelected() && this.serverName().length === 0;
 		}, this);
 
 		this.isPortNumberInvalid = ko.pureComputed(function() {
 			const portNumber = parseInt(this.portNumber());
 
-			return isNaN(portNumber) || portNumber < 0 || portNumber > 65535;
+			return !this.isProfileSelected() && (isNaN(portNumber) || portNumber < 0 || portNumber > 65535);
 		}, this);
 
 		this.isUserNameInvalid = ko.pureComputed(function() {
-			return this.userName().length === 0;
+			return !this.isProfileSelected() && this.userName().length === 0;
 		}, this);
 
 		this.isDatabaseInvalid = ko.pureComputed(function() {
@@ -74,6 +99,10 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 			);
 		}, this);
 
+		this.hasMoreStages = ko.pureComputed(function() {
+			return this.stageList().length < this.stageTotal();
+		}, this);
+
 		this.isFormInvalid = ko.pureComputed(function() {
 			return (
 				this.isServerNameInvalid() ||
@@ -95,7 +124,9 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 		this.settingsUrl = ko.pureComputed(function() {
 			let queryParams = [];
 
-			if (this.serverName() !== undefined && this.serverName().length !== 0) {
+			if (this.profileSelected() !== undefined) {
+				queryParams.push({ name: "profile", value: this.profileSelected() });
+			} else if (this.serverName() !== undefined && this.serverName().length !== 0) {
 				queryParams.push({ name: "server_name", value: this.serverName() });
 			}
 
@@ -107,10 +138,6 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 				queryParams.push({ name: "user_name", value: this.userName() });
 			}
 
-			if (this.password() !== undefined && this.password().length !== 0) {
-				queryParams.push({ name: "password", value: this.password() });
-			}
-
 			if (this.databaseSelected() !== undefined) {
 				queryParams.push({ name: "database", value: this.databaseSelected() });
 			}
@@ -119,6 +146,10 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 				queryParams.push({ name: "stage", value: this.stageSelected() });
 			}
 
+			if (this.criticalPath()) {
+				queryParams.push({ name: "critical_path", value: "1" });
+			}
+
 			const href = queryParams
 				.map(function(value) {
 					return encodeURIComponent(value.name) + "=" + encodeURIComponent(value.value);
@@ -134,21 +165,70 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 					this.loadStages();
 				} else {
 					this.stageList([]);
+					this.stageTotal(0);
 				}
 			}.bind(this)
 		);
 
-		this.updateForm();
+		this.loadProfiles();
+	};
+
+	Connect.prototype.connectionParams = function() {
+		if (this.profileSelected() !== undefined) {
+			return { profile: this.profileSelected() };
+		} else {
+			return {
+				server: this.serverName(),
+				port: parseInt(this.portNumber()),
+				user: this.userName(),
+				password: this.password(),
+			};
+		}
+	};
+
+	Connect.prototype.loadProfiles = function() {
+		reqwest({
+			url: "/api/v1/profiles",
+			type: "json",
+			method: "POST",
+			contentType: "application/json",
+			data: JSON.stringify({}),
+		})
+			.then(
+				function(resp) {
+					if (resp.success) {
+						this.profileList(resp.result.profiles);
+						this.allowCredentials(resp.result.allow_credentials);
+						this.setReady();
+						this.updateForm();
+					} else {
+						this.setError(resp.message);
+					}
+				}.bind(this)
+			)
+			.fail(
+				function(resp) {
+					this.setError(errorMessage(resp));
+				}.bind(this)
+			);
+
+		this.setLoading();
 	};
 
 	Connect.prototype.updateForm = function() {
 		const queryParams = getQueryParams();
+		const isProfilePresent = "profile" in queryParams;
 		const isServerNamePresent = "server_name" in queryParams;
 		const isPortNumberPresent = "port_number" in queryParams;
 		const isUserNamePresent = "user_name" in queryParams;
-		const isPasswordPresent = "password" in queryParams;
 		const isDatabasePresent = "database" in queryParams;
 		const isStagePresent = "stage" in queryParams;
+		const isConnectionPresent =
+			isProfilePresent || (isServerNamePresent && isPortNumberPresent && isUserNamePresent);
+
+		if (isProfilePresent) {
+			this.profileSelected(queryParams["profile"]);
+		}
 
 		if (isServerNamePresent) {
 			this.serverName(queryParams["server_name"]);
@@ -162,14 +242,10 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 			this.userName(queryParams["user_name"]);
 		}
 
-		if (isPasswordPresent) {
-			this.password(queryParams["password"]);
-		}
-
 		if (isDatabasePresent) {
 			const databaseName = queryParams["database"];
 
-			this.databaseList([databaseName]);
+			this.databaseList([{ name: databaseName, label: databaseName }]);
 			this.databaseSelected(databaseName);
 		}
 
@@ -180,24 +256,13 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 			this.stageSelected(stageName);
 		}
 
-		if (
-			isServerNamePresent &&
-			isPortNumberPresent &&
-			isUserNamePresent &&
-			isPasswordPresent &&
-			isDatabasePresent &&
-			isStagePresent
-		) {
+		this.criticalPath(queryParams["critical_path"] === "1");
+
+		if (isConnectionPresent && isDatabasePresent && isStagePresent) {
 			this.loadData();
-		} else if (
-			isServerNamePresent &&
-			isPortNumberPresent &&
-			isUserNamePresent &&
-			isPasswordPresent &&
-			isDatabasePresent
-		) {
+		} else if (isConnectionPresent && isDatabasePresent) {
 			this.loadStages();
-		} else if (isServerNamePresent && isPortNumberPresent && isUserNamePresent && isPasswordPresent) {
+		} else if (isConnectionPresent) {
 			this.loadDatabases();
 		}
 	};
@@ -221,17 +286,28 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 			type: "json",
 			method: "POST",
 			contentType: "application/json",
-			data: JSON.stringify({
-				server: this.serverName(),
-				port: parseInt(this.portNumber()),
-				user: this.userName(),
-				password: this.password(),
-			}),
+			data: JSON.stringify(
+				Object.assign(this.connectionParams(), {
+					check: "mark",
+				})
+			),
 		})
 			.then(
 				function(resp) {
 					if (resp.success) {
-						this.databaseList(resp.result);
+						const databases = resp.result.map(function(database) {
+							return {
+								name: database.name,
+								label: database.gantt ? database.name : database.name + " (no data)",
+								gantt: database.gantt,
+							};
+						});
+
+						databases.sort(function(a, b) {
+							return b.gantt - a.gantt;
+						});
+
+						this.databaseList(databases);
 						this.setReady();
 					} else {
 						this.setError(resp.message);
@@ -240,7 +316,7 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 			)
 			.fail(
 				function(resp) {
-					this.setError(resp.responseText);
+					this.setError(errorMessage(resp));
 				}.bind(this)
 			);
 
@@ -248,23 +324,38 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 	};
 
 	Connect.prototype.loadStages = function() {
+		this.fetchStages(0);
+	};
+
+	Connect.prototype.loadMoreStages = function() {
+		this.fetchStages(this.stageList().length);
+	};
+
+	Connect.prototype.fetchStages = function(offset) {
 		reqwest({
 			url: "/api/v1/stages",
 			type: "json",
 			method: "POST",
 			contentType: "application/json",
-			data: JSON.stringify({
-				server: this.serverName(),
-				port: parseInt(this.portNumber()),
-				user: this.userName(),
-				password: this.password(),
-				database: this.databaseSelected(),
-			}),
+			data: JSON.stringify(
+				Object.assign(this.connectionParams(), {
+					database: this.databaseSelected(),
+					filter: this.stageFilter(),
+					order: "recent",
+					limit: STAGE_PAGE_SIZE,
+					offset: offset,
+				})
+			),
 		})
 			.then(
 				function(resp) {
 					if (resp.success) {
-						this.stageList(resp.result);
+						const stages = resp.result.stages.map(function(stage) {
+							return stage.name;
+						});
+
+						this.stageList(offset === 0 ? stages : this.stageList().concat(stages));
+						this.stageTotal(resp.result.total);
 						this.setReady();
 					} else {
 						this.setError(resp.message);
@@ -273,7 +364,7 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 			)
 			.fail(
 				function(resp) {
-					this.setError(resp.responseText);
+					this.setError(errorMessage(resp));
 				}.bind(this)
 			);
 
@@ -285,19 +376,21 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 			url: "/api/v1/data",
 			method: "POST",
 			contentType: "application/json",
-			data: JSON.stringify({
-				server: this.serverName(),
-				port: parseInt(this.portNumber()),
-				user: this.userName(),
-				password: this.password(),
-				database: this.databaseSelected(),
-				stage: this.stageSelected(),
-			}),
+			data: JSON.stringify(
+				Object.assign(this.connectionParams(), {
+					database: this.databaseSelected(),
+					stage: this.stageSelected(),
+					format: "dlm",
+					critical_path: this.criticalPath(),
+				})
+			),
 		})
 			.then(
 				function(resp) {
 					const mappings = Storage.getGroupMapping();
-					const items = d3.dsvFormat(";").parseRows(resp, function(row) {
+					// Rows skipped by the server follow after an empty line.
+					const rows = resp.split("\n\n")[0];
+					const items = d3.dsvFormat(";").parseRows(rows, function(row) {
 						const groupName = row[3];
 
 						return new Target({
@@ -306,6 +399,10 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 							endTime: parseFloat(row[2]),
 							groupName: groupName in mappings ? mappings[groupName] : groupName,
 							threadName: row[4],
+							running: row[5] === "1",
+							critical: row[6] === "1",
+							kind: row[7],
+							depth: parseInt(row[8], 10) || 0,
 						});
 					});
 
@@ -315,7 +412,7 @@ define(["knockout", "reqwest", "d3", "Storage", "Target"], function(ko, reqwest,
 			)
 			.fail(
 				function(resp) {
-					this.setError(resp.responseText);
+					this.setError(errorMessage(resp));
 				}.bind(this)
 			);
 
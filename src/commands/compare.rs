@@ -0,0 +1,26 @@
+use super::connect;
+use super::CommandError;
+use super::CommandResult;
+use super::Output;
+use crate::config::Config;
+use crate::options::CompareCommand;
+use crate::stats;
+
+/// Prints comparison of two stages as pretty formatted JSON.
+pub fn compare(config: &Config, command: &CompareCommand) -> CommandResult<()> {
+    let (postgres, database) = connect(config, &command.stage)?;
+    let current_database = command.with_database.as_deref().unwrap_or(&database);
+    let comparison = stats::compare(
+        &postgres,
+        (&database, &command.stage.stage),
+        (current_database, &command.with_stage),
+    )
+    .map_err(CommandError::database_error)?;
+    let mut output = Output::open(command.stage.output.as_deref())?;
+    let path = output.path.clone();
+
+    serde_json::to_writer_pretty(&mut output.writer, &comparison)
+        .map_err(|error| CommandError::write_error(&path, error.into()))?;
+    output.write_all(b"\n")?;
+    output.finish()
+}
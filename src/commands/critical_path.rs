@@ -0,0 +1,21 @@
+use super::connect;
+use super::CommandError;
+use super::CommandResult;
+use super::Output;
+use crate::config::Config;
+use crate::options::CriticalPathCommand;
+use crate::stats;
+
+/// Prints critical path of the stage as pretty formatted JSON.
+pub fn critical_path(config: &Config, command: &CriticalPathCommand) -> CommandResult<()> {
+    let (postgres, database) = connect(config, &command.stage)?;
+    let critical_path = stats::critical_path(&postgres, &database, &command.stage.stage)
+        .map_err(CommandError::database_error)?;
+    let mut output = Output::open(command.stage.output.as_deref())?;
+    let path = output.path.clone();
+
+    serde_json::to_writer_pretty(&mut output.writer, &critical_path)
+        .map_err(|error| CommandError::write_error(&path, error.into()))?;
+    output.write_all(b"\n")?;
+    output.finish()
+}
@@ -0,0 +1,100 @@
+use crate::database::DatabaseError;
+use crate::render::RenderError;
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::io::Error as IoError;
+
+pub type CommandResult<T> = Result<T, CommandError>;
+
+const EXIT_INVALID_ARGUMENTS: i32 = 1;
+const EXIT_WRITE_FAILED: i32 = 2;
+const EXIT_RENDER_FAILED: i32 = 3;
+const EXIT_CONNECTION_FAILED: i32 = 10;
+const EXIT_INVALID_MAPPING: i32 = 11;
+const EXIT_QUERY_FAILED: i32 = 12;
+const EXIT_INVALID_DATA: i32 = 13;
+
+#[derive(Debug)]
+pub enum CommandError {
+    UnknownProfile { name: String },
+    DatabaseRequired,
+    DatabaseError { error: DatabaseError },
+    RenderError { error: RenderError },
+    WriteError { path: String, message: String },
+}
+
+impl CommandError {
+    pub fn unknown_profile(name: &str) -> CommandError {
+        CommandError::UnknownProfile { name: name.into() }
+    }
+
+    pub fn database_required() -> CommandError {
+        CommandError::DatabaseRequired
+    }
+
+    pub fn database_error(error: DatabaseError) -> CommandError {
+        CommandError::DatabaseError { error }
+    }
+
+    pub fn render_error(error: RenderError) -> CommandError {
+        CommandError::RenderError { error }
+    }
+
+    pub fn write_error(path: &str, error: IoError) -> CommandError {
+        CommandError::WriteError {
+            path: path.into(),
+            message: format!("{}", error),
+        }
+    }
+
+    /// Process exit code, database errors are split by kind so scripts can tell unavailable
+    /// server from wrong mapping or bad data.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CommandError::UnknownProfile { .. } | CommandError::DatabaseRequired => {
+                EXIT_INVALID_ARGUMENTS
+            }
+            CommandError::WriteError { .. } => EXIT_WRITE_FAILED,
+            CommandError::RenderError { .. } => EXIT_RENDER_FAILED,
+            CommandError::DatabaseError { error } => match error {
+                DatabaseError::ConnectionError { .. }
+                | DatabaseError::ConnectionRefused { .. }
+                | DatabaseError::AuthenticationFailed { .. }
+                | DatabaseError::DatabaseNotExists { .. }
+                | DatabaseError::Timeout { .. } => EXIT_CONNECTION_FAILED,
+                DatabaseError::SchemaNotExists { .. }
+                | DatabaseError::TableNotExists { .. }
+                | DatabaseError::MappedColumnNotExists { .. }
+                | DatabaseError::InvalidTimeZone { .. }
+                | DatabaseError::DependenciesNotMapped => EXIT_INVALID_MAPPING,
+                DatabaseError::QueryExecutionError { .. }
+                | DatabaseError::PrepareQueryError { .. }
+                | DatabaseError::TransactionError { .. }
+                | DatabaseError::ColumnNotExists => EXIT_QUERY_FAILED,
+                DatabaseError::ConversionError { .. }
+                | DatabaseError::TimeParseError { .. }
+                | DatabaseError::NullValue { .. }
+                | DatabaseError::InvalidInterval { .. }
+                | DatabaseError::TooManyRowErrors { .. } => EXIT_INVALID_DATA,
+            },
+        }
+    }
+}
+
+impl Error for CommandError {}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            CommandError::UnknownProfile { name } => write!(f, "Unknown profile `{}`", name),
+            CommandError::DatabaseRequired => write!(f, "Database name required"),
+            CommandError::DatabaseError { error } => write!(f, "{}", error),
+            CommandError::RenderError { error } => write!(f, "{}", error),
+            CommandError::WriteError { path, message } => {
+                write!(f, "Failed to write {}: {}", path, message)
+            }
+        }
+    }
+}
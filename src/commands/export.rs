@@ -0,0 +1,68 @@
+use super::connect;
+use super::CommandError;
+use super::CommandResult;
+use super::Output;
+use crate::config::Config;
+use crate::export::read_events;
+use crate::export::ExportOptions;
+use crate::export::Nesting;
+use crate::export::Row;
+use crate::options::ExportCommand;
+use crate::stats;
+
+/// Streams stage targets in selected format without keeping them in memory. Targets are read at
+/// once when critical path is requested, because it depends on all of them.
+pub fn export(config: &Config, command: &ExportCommand) -> CommandResult<()> {
+    let (postgres, database) = connect(config, &command.stage)?;
+    let options = ExportOptions {
+        stage: command.stage.stage.clone(),
+        trace_process: command.trace_process,
+    };
+    let mut row_writer = command.format.writer(&options);
+    let mut output = Output::open(command.stage.output.as_deref())?;
+    let path = output.path.clone();
+    let writer = &mut output.writer;
+
+    row_writer
+        .write_header(writer)
+        .map_err(|error| CommandError::write_error(&path, error))?;
+
+    if command.critical_path {
+        let rows = stats::critical_rows(&postgres, &database, &command.stage.stage)
+            .map_err(CommandError::database_error)?;
+
+        for row in &rows {
+            row_writer
+                .write_row(writer, row)
+                .map_err(|error| CommandError::write_error(&path, error))?;
+        }
+    } else {
+        let mut nesting = Nesting::new();
+        let mut write = |row: Row| row_writer.write_row(writer, &row);
+
+        postgres
+            .data(&database, &command.stage.stage, |target| {
+                nesting.push(Row::from_target(target), &mut write)
+            })
+            .map_err(CommandError::database_error)?
+            .map_err(|error| CommandError::write_error(&path, error))?;
+        nesting
+            .finish(&mut write)
+            .map_err(|error| CommandError::write_error(&path, error))?;
+    }
+
+    let events = read_events(&postgres, &database, &command.stage.stage)
+        .map_err(CommandError::database_error)?;
+
+    for row in &events {
+        row_writer
+            .write_row(writer, row)
+            .map_err(|error| CommandError::write_error(&path, error))?;
+    }
+
+    row_writer
+        .write_footer(writer)
+        .map_err(|error| CommandError::write_error(&path, error))?;
+
+    output.finish()
+}
@@ -0,0 +1,27 @@
+use super::connect;
+use super::CommandError;
+use super::CommandResult;
+use super::Output;
+use crate::config::Config;
+use crate::options::InferredPathCommand;
+use crate::stats;
+
+/// Prints inferred chain of targets as pretty formatted JSON.
+pub fn inferred_path(config: &Config, command: &InferredPathCommand) -> CommandResult<()> {
+    let (postgres, database) = connect(config, &command.stage)?;
+    let inferred_path = stats::inferred_path(
+        &postgres,
+        &database,
+        &command.stage.stage,
+        i64::from(command.tolerance),
+        command.scope,
+    )
+    .map_err(CommandError::database_error)?;
+    let mut output = Output::open(command.stage.output.as_deref())?;
+    let path = output.path.clone();
+
+    serde_json::to_writer_pretty(&mut output.writer, &inferred_path)
+        .map_err(|error| CommandError::write_error(&path, error.into()))?;
+    output.write_all(b"\n")?;
+    output.finish()
+}
@@ -0,0 +1,103 @@
+mod compare;
+mod critical_path;
+mod error;
+mod export;
+mod inferred_path;
+mod quality;
+mod render;
+mod simulate;
+mod stats;
+mod utilization;
+
+pub use self::compare::compare;
+pub use self::critical_path::critical_path;
+pub use self::error::CommandError;
+pub use self::error::CommandResult;
+pub use self::export::export;
+pub use self::inferred_path::inferred_path;
+pub use self::quality::quality;
+pub use self::render::render;
+pub use self::simulate::simulate;
+pub use self::stats::stats;
+pub use self::utilization::utilization;
+
+use crate::config::Config;
+use crate::database::PostgreSQL;
+use crate::options::StageArgs;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+
+const STDOUT_PATH: &str = "-";
+
+/// Connects using profile from configuration, returns connection and database name.
+fn connect(config: &Config, args: &StageArgs) -> CommandResult<(PostgreSQL, String)> {
+    let profile = config
+        .profile(&args.profile)
+        .ok_or_else(|| CommandError::unknown_profile(&args.profile))?;
+    let database = args
+        .database
+        .as_deref()
+        .or_else(|| profile.database())
+        .ok_or_else(CommandError::database_required)?;
+    let settings = config.database();
+    let mut postgres = profile
+        .postgres()
+        .with_fetch_size(settings.fetch_size)
+        .with_default_database(&settings.default_database);
+
+    if let Some(policy) = args.invalid_intervals {
+        postgres = postgres.with_interval_policy(policy);
+    }
+
+    if let Some(max_row_errors) = args.max_row_errors {
+        postgres = postgres.with_max_row_errors(max_row_errors);
+    }
+
+    Ok((postgres, database.into()))
+}
+
+/// Output file or stdout when file is not given or equals to `-`.
+struct Output {
+    path: String,
+    writer: Box<dyn Write>,
+}
+
+impl Output {
+    fn open(path: Option<&Path>) -> CommandResult<Output> {
+        match path {
+            Some(path) if path != Path::new(STDOUT_PATH) => {
+                let display = path.display().to_string();
+                let file = File::create(path)
+                    .map_err(|error| CommandError::write_error(&display, error))?;
+
+                Ok(Output {
+                    path: display,
+                    writer: Box::new(BufWriter::new(file)),
+                })
+            }
+            _ => Ok(Output {
+                path: STDOUT_PATH.into(),
+                writer: Box::new(BufWriter::new(io::stdout())),
+            }),
+        }
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> CommandResult<()> {
+        let path = &self.path;
+
+        self.writer
+            .write_all(data)
+            .map_err(|error| CommandError::write_error(path, error))
+    }
+
+    fn finish(mut self) -> CommandResult<()> {
+        let path = &self.path;
+
+        self.writer
+            .flush()
+            .map_err(|error| CommandError::write_error(path, error))
+    }
+}
@@ -0,0 +1,61 @@
+use super::connect;
+use super::CommandError;
+use super::CommandResult;
+use super::Output;
+use crate::config::Config;
+use crate::export;
+use crate::options::RenderCommand;
+use crate::render;
+use crate::render::ImageFormat;
+use crate::render::RenderOptions;
+use std::path::Path;
+
+const SVG_EXTENSION: &str = "svg";
+
+/// Renders stage chart and writes image to output file or stdout.
+pub fn render(config: &Config, command: &RenderCommand) -> CommandResult<()> {
+    let mut options = RenderOptions::default();
+
+    if let Some(width) = command.width {
+        options.width = width;
+    }
+
+    options.height = command.height;
+
+    if let Some(dpi) = command.dpi {
+        options.dpi = dpi;
+    }
+
+    if let Some(theme) = command.theme {
+        options.theme = theme;
+    }
+
+    options.group_mapping = command.rename_group.iter().cloned().collect();
+    options.validate().map_err(CommandError::render_error)?;
+
+    let output_path = command.stage.output.as_deref();
+    let format = command
+        .format
+        .unwrap_or_else(|| match output_path.and_then(Path::extension) {
+            Some(extension) if extension == SVG_EXTENSION => ImageFormat::Svg,
+            _ => ImageFormat::Png,
+        });
+    let (postgres, database) = connect(config, &command.stage)?;
+    let mut rows = export::read_rows(&postgres, &database, &command.stage.stage)
+        .map_err(CommandError::database_error)?;
+
+    rows.extend(
+        export::read_events(&postgres, &database, &command.stage.stage)
+            .map_err(CommandError::database_error)?,
+    );
+    let image = match format {
+        ImageFormat::Svg => render::render_svg(&rows, &options).map(String::into_bytes),
+        ImageFormat::Png => render::load_font(&config.render().font)
+            .and_then(|font| render::render_png(&rows, &options, &font)),
+    }
+    .map_err(CommandError::render_error)?;
+    let mut output = Output::open(output_path)?;
+
+    output.write_all(&image)?;
+    output.finish()
+}
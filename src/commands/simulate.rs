@@ -0,0 +1,56 @@
+use super::connect;
+use super::CommandError;
+use super::CommandResult;
+use super::Output;
+use crate::config::Config;
+use crate::export::ExportOptions;
+use crate::options::SimulateCommand;
+use crate::stats;
+
+/// Prints simulation summary as pretty formatted JSON or only simulated timeline in selected data
+/// format.
+pub fn simulate(config: &Config, command: &SimulateCommand) -> CommandResult<()> {
+    let (postgres, database) = connect(config, &command.stage)?;
+    let simulation = stats::simulate(
+        &postgres,
+        &database,
+        &command.stage.stage,
+        command.workers,
+        command.policy,
+    )
+    .map_err(CommandError::database_error)?;
+    let mut output = Output::open(command.stage.output.as_deref())?;
+    let path = output.path.clone();
+
+    match command.format {
+        Some(format) => {
+            let options = ExportOptions {
+                stage: command.stage.stage.clone(),
+                ..ExportOptions::default()
+            };
+            let mut row_writer = format.writer(&options);
+            let writer = &mut output.writer;
+
+            row_writer
+                .write_header(writer)
+                .map_err(|error| CommandError::write_error(&path, error))?;
+
+            for row in &simulation.rows {
+                row_writer
+                    .write_row(writer, row)
+                    .map_err(|error| CommandError::write_error(&path, error))?;
+            }
+
+            row_writer
+                .write_footer(writer)
+                .map_err(|error| CommandError::write_error(&path, error))?;
+        }
+        None => {
+            serde_json::to_writer_pretty(&mut output.writer, &simulation)
+                .map_err(|error| CommandError::write_error(&path, error.into()))?;
+            output.write_all(b"\n")?;
+        }
+    }
+
+    output.finish()
+}
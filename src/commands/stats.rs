@@ -0,0 +1,21 @@
+use super::connect;
+use super::CommandError;
+use super::CommandResult;
+use super::Output;
+use crate::config::Config;
+use crate::options::StatsCommand;
+use crate::stats;
+
+/// Prints stage statistics as pretty formatted JSON.
+pub fn stats(config: &Config, command: &StatsCommand) -> CommandResult<()> {
+    let (postgres, database) = connect(config, &command.stage)?;
+    let stats = stats::collect(&postgres, &database, &command.stage.stage, command.longest)
+        .map_err(CommandError::database_error)?;
+    let mut output = Output::open(command.stage.output.as_deref())?;
+    let path = output.path.clone();
+
+    serde_json::to_writer_pretty(&mut output.writer, &stats)
+        .map_err(|error| CommandError::write_error(&path, error.into()))?;
+    output.write_all(b"\n")?;
+    output.finish()
+}
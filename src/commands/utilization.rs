@@ -0,0 +1,27 @@
+use super::connect;
+use super::CommandError;
+use super::CommandResult;
+use super::Output;
+use crate::config::Config;
+use crate::options::UtilizationCommand;
+use crate::stats;
+
+/// Prints thread utilization of the stage as pretty formatted JSON.
+pub fn utilization(config: &Config, command: &UtilizationCommand) -> CommandResult<()> {
+    let (postgres, database) = connect(config, &command.stage)?;
+    let utilization = stats::utilization(
+        &postgres,
+        &database,
+        &command.stage.stage,
+        command.buckets,
+        command.min_gap,
+    )
+    .map_err(CommandError::database_error)?;
+    let mut output = Output::open(command.stage.output.as_deref())?;
+    let path = output.path.clone();
+
+    serde_json::to_writer_pretty(&mut output.writer, &utilization)
+        .map_err(|error| CommandError::write_error(&path, error.into()))?;
+    output.write_all(b"\n")?;
+    output.finish()
+}
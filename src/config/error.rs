@@ -0,0 +1,81 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::io::Error as IoError;
+use std::path::Path;
+use toml::de::Error as TomlError;
+use toml::ser::Error as TomlSerializeError;
+
+pub type ConfigResult<T> = Result<T, ConfigError>;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    ReadError { path: String, message: String },
+    ParseError { path: String, message: String },
+    PasswordFileNotRead { profile: String, message: String },
+    InvalidValue { key: String, message: String },
+    SerializeError { message: String },
+}
+
+impl ConfigError {
+    pub fn read_error(path: &Path, error: IoError) -> ConfigError {
+        ConfigError::ReadError {
+            path: path.display().to_string(),
+            message: format!("{}", error),
+        }
+    }
+
+    pub fn parse_error(path: &Path, error: TomlError) -> ConfigError {
+        ConfigError::ParseError {
+            path: path.display().to_string(),
+            message: format!("{}", error),
+        }
+    }
+
+    pub fn password_file_error(profile: &str, error: IoError) -> ConfigError {
+        ConfigError::PasswordFileNotRead {
+            profile: profile.into(),
+            message: format!("{}", error),
+        }
+    }
+
+    pub fn invalid_value(key: &str, message: &str) -> ConfigError {
+        ConfigError::InvalidValue {
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn serialize_error(error: TomlSerializeError) -> ConfigError {
+        ConfigError::SerializeError {
+            message: format!("{}", error),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            ConfigError::ReadError { path, message } => {
+                write!(f, "Failed to read configuration `{}`: {}", path, message)
+            }
+            ConfigError::ParseError { path, message } => {
+                write!(f, "Failed to parse configuration `{}`: {}", path, message)
+            }
+            ConfigError::PasswordFileNotRead { profile, message } => write!(
+                f,
+                "Failed to read password file of profile `{}`: {}",
+                profile, message
+            ),
+            ConfigError::InvalidValue { key, message } => {
+                write!(f, "Invalid configuration value `{}`: {}", key, message)
+            }
+            ConfigError::SerializeError { message } => {
+                write!(f, "Failed to serialize configuration: {}", message)
+            }
+        }
+    }
+}
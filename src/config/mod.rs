@@ -0,0 +1,211 @@
+mod error;
+mod profile;
+mod sections;
+
+pub use self::error::ConfigError;
+pub use self::error::ConfigResult;
+pub use self::profile::Profile;
+pub use self::sections::CacheConfig;
+pub use self::sections::DatabaseConfig;
+pub use self::sections::LoggingConfig;
+pub use self::sections::RenderConfig;
+pub use self::sections::ServerConfig;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    allow_credentials: bool,
+    #[serde(default)]
+    server: ServerConfig,
+    #[serde(default)]
+    database: DatabaseConfig,
+    #[serde(default)]
+    cache: CacheConfig,
+    #[serde(default)]
+    logging: LoggingConfig,
+    #[serde(default)]
+    render: RenderConfig,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> ConfigResult<Config> {
+        let content =
+            fs::read_to_string(path).map_err(|error| ConfigError::read_error(path, error))?;
+        let mut config: Config =
+            toml::from_str(&content).map_err(|error| ConfigError::parse_error(path, error))?;
+
+        for (name, profile) in &mut config.profiles {
+            profile.load_password(name)?;
+        }
+
+        Ok(config)
+    }
+
+    /// Checks values which can not be verified by deserialization, should be called after all
+    /// overrides applied.
+    pub fn validate(&self) -> ConfigResult<()> {
+        if self.database.fetch_size <= 0 {
+            return Err(ConfigError::invalid_value(
+                "database.fetch_size",
+                "must be positive",
+            ));
+        }
+
+        if self.database.default_database.is_empty() {
+            return Err(ConfigError::invalid_value(
+                "database.default_database",
+                "must not be empty",
+            ));
+        }
+
+        for (name, profile) in &self.profiles {
+            profile.validate(name)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks settings used only by HTTP server.
+    pub fn validate_server(&self) -> ConfigResult<()> {
+        if self.server.port == 0 {
+            return Err(ConfigError::invalid_value(
+                "server.port",
+                "must not be zero",
+            ));
+        }
+
+        if !self.server.static_dir.is_dir() {
+            return Err(ConfigError::invalid_value(
+                "server.static_dir",
+                "must be an existing directory",
+            ));
+        }
+
+        if !self.server.temp_dir.is_dir() {
+            return Err(ConfigError::invalid_value(
+                "server.temp_dir",
+                "must be an existing directory",
+            ));
+        }
+
+        if self.server.max_streams == 0 {
+            return Err(ConfigError::invalid_value(
+                "server.max_streams",
+                "must not be zero",
+            ));
+        }
+
+        if self.server.stream_lifetime == 0 {
+            return Err(ConfigError::invalid_value(
+                "server.stream_lifetime",
+                "must not be zero",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Effective configuration in TOML format, passwords are masked.
+    pub fn to_toml(&self) -> ConfigResult<String> {
+        toml::to_string_pretty(self).map_err(ConfigError::serialize_error)
+    }
+
+    pub fn allow_credentials(&self) -> bool {
+        self.allow_credentials
+    }
+
+    pub fn set_allow_credentials(&mut self, value: bool) {
+        self.allow_credentials = value;
+    }
+
+    pub fn server(&self) -> &ServerConfig {
+        &self.server
+    }
+
+    pub fn server_mut(&mut self) -> &mut ServerConfig {
+        &mut self.server
+    }
+
+    pub fn database(&self) -> &DatabaseConfig {
+        &self.database
+    }
+
+    pub fn cache(&self) -> &CacheConfig {
+        &self.cache
+    }
+
+    pub fn logging(&self) -> &LoggingConfig {
+        &self.logging
+    }
+
+    pub fn logging_mut(&mut self) -> &mut LoggingConfig {
+        &mut self.logging
+    }
+
+    pub fn render(&self) -> &RenderConfig {
+        &self.render
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [profiles.production]
+        host = "localhost"
+        user = "gantt"
+        password = "secret"
+        database = "builds"
+
+        [profiles.production.mapping]
+        table = "jobs"
+        invalid_intervals = "drop"
+        max_row_errors = 5
+        time = { time_zone = "+03:00" }
+
+        [profiles.production.mapping.dependencies]
+        column = "depends_on"
+
+        [profiles.production.mapping.hierarchy]
+        id = "id"
+        parent = "parent_id"
+
+        [profiles.production.mapping.events]
+        table = "job_event"
+    "#;
+
+    #[test]
+    fn to_toml_round_trip() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        let printed = config.to_toml().unwrap();
+        let parsed: Config = toml::from_str(&printed).unwrap();
+        let value: toml::Value = toml::from_str(&printed).unwrap();
+        let profile = &value["profiles"]["production"];
+        let mapping = &profile["mapping"];
+
+        assert_eq!(mapping["invalid_intervals"].as_str(), Some("drop"));
+        assert_eq!(mapping["max_row_errors"].as_integer(), Some(5));
+        assert_eq!(mapping["time"]["time_zone"].as_str(), Some("+03:00"));
+        assert_eq!(
+            mapping["dependencies"]["column"].as_str(),
+            Some("depends_on")
+        );
+        assert_eq!(profile["password"].as_str(), Some("********"));
+        assert_eq!(parsed.to_toml().unwrap(), printed);
+    }
+}
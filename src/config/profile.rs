@@ -0,0 +1,98 @@
+use super::ConfigError;
+use super::ConfigResult;
+use crate::database::PostgreSQL;
+use crate::database::SourceMapping;
+use serde::Serializer;
+use std::fs;
+use std::path::PathBuf;
+
+const DEFAULT_PORT: u16 = 5432;
+const MASKED_PASSWORD: &str = "********";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    host: String,
+    #[serde(default = "default_port")]
+    port: u16,
+    user: String,
+    #[serde(default, serialize_with = "mask_password")]
+    password: Option<String>,
+    #[serde(default, skip_serializing)]
+    password_file: Option<PathBuf>,
+    #[serde(default)]
+    database: Option<String>,
+    #[serde(default)]
+    mapping: SourceMapping,
+}
+
+impl Profile {
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+
+    pub fn validate(&self, name: &str) -> ConfigResult<()> {
+        let key = |field: &str| format!("profiles.{}.{}", name, field);
+
+        if self.host.is_empty() {
+            return Err(ConfigError::invalid_value(
+                &key("host"),
+                "must not be empty",
+            ));
+        }
+
+        if self.user.is_empty() {
+            return Err(ConfigError::invalid_value(
+                &key("user"),
+                "must not be empty",
+            ));
+        }
+
+        if let Err(err) = self.mapping.check() {
+            return Err(ConfigError::invalid_value(
+                &key("mapping"),
+                &err.to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn postgres(&self) -> PostgreSQL {
+        let password = self.password.as_deref().unwrap_or("");
+
+        PostgreSQL::new(&self.host, self.port, &self.user, password).with_mapping(&self.mapping)
+    }
+
+    /// Replaces `password_file` with its content, so file is read only once at startup.
+    pub fn load_password(&mut self, name: &str) -> ConfigResult<()> {
+        if self.password.is_some() && self.password_file.is_some() {
+            return Err(ConfigError::invalid_value(
+                &format!("profiles.{}.password_file", name),
+                "must not be used together with password",
+            ));
+        }
+
+        if let Some(path) = self.password_file.take() {
+            let password = fs::read_to_string(&path)
+                .map_err(|error| ConfigError::password_file_error(name, error))?;
+
+            self.password = Some(password.trim_end_matches(&['\r', '\n'][..]).into());
+        }
+
+        Ok(())
+    }
+}
+
+fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+fn mask_password<S>(password: &Option<String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match password {
+        Some(_) => serializer.serialize_some(MASKED_PASSWORD),
+        None => serializer.serialize_none(),
+    }
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+const DEFAULT_ADDRESS: &str = "localhost";
+const DEFAULT_PORT: u16 = 8080;
+const DEFAULT_STATIC_DIR: &str = "public";
+const DEFAULT_TEMP_DIR: &str = ".";
+const DEFAULT_MAX_STREAMS: usize = 16;
+const DEFAULT_STREAM_LIFETIME: u64 = 3_600;
+const DEFAULT_FETCH_SIZE: i32 = 1_000;
+const DEFAULT_DATABASE: &str = "postgres";
+const DEFAULT_STATIC_MAX_AGE: u64 = 0;
+const DEFAULT_FONT: &str = "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub address: String,
+    pub port: u16,
+    pub static_dir: PathBuf,
+    pub temp_dir: PathBuf,
+    pub max_streams: usize,
+    pub stream_lifetime: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> ServerConfig {
+        ServerConfig {
+            address: DEFAULT_ADDRESS.into(),
+            port: DEFAULT_PORT,
+            static_dir: DEFAULT_STATIC_DIR.into(),
+            temp_dir: DEFAULT_TEMP_DIR.into(),
+            max_streams: DEFAULT_MAX_STREAMS,
+            stream_lifetime: DEFAULT_STREAM_LIFETIME,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub fetch_size: i32,
+    pub default_database: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> DatabaseConfig {
+        DatabaseConfig {
+            fetch_size: DEFAULT_FETCH_SIZE,
+            default_database: DEFAULT_DATABASE.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub static_max_age: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> CacheConfig {
+        CacheConfig {
+            static_max_age: DEFAULT_STATIC_MAX_AGE,
+        }
+    }
+}
+
+/// Log filter in `env_logger` format, `RUST_LOG` is used only when the level is not configured.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderConfig {
+    pub font: PathBuf,
+}
+
+impl Default for RenderConfig {
+    fn default() -> RenderConfig {
+        RenderConfig {
+            font: DEFAULT_FONT.into(),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+const DEFAULT_SEPARATOR: &str = ",";
+const DEFAULT_STAGE: &str = "maker_name";
+const DEFAULT_TARGET: &str = "name";
+const DEFAULT_PREREQUISITE: &str = "prerequisite";
+
+/// Source of edges between targets. Either a column of the mapped table with names of
+/// prerequisite targets (text array or text separated by `separator`) or a separate table in the
+/// mapped schema with one row per edge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DependencyMapping {
+    Column {
+        column: String,
+        #[serde(default = "default_separator")]
+        separator: String,
+    },
+    Table {
+        table: String,
+        #[serde(default = "default_stage")]
+        stage: String,
+        #[serde(default = "default_target")]
+        target: String,
+        #[serde(default = "default_prerequisite")]
+        prerequisite: String,
+    },
+}
+
+/// Target `target` can start only after all targets named `prerequisite` are finished.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Dependency {
+    pub target: String,
+    pub prerequisite: String,
+}
+
+#[cfg(test)]
+impl Dependency {
+    pub fn new(target: &str, prerequisite: &str) -> Dependency {
+        Dependency {
+            target: target.into(),
+            prerequisite: prerequisite.into(),
+        }
+    }
+}
+
+fn default_separator() -> String {
+    DEFAULT_SEPARATOR.into()
+}
+
+fn default_stage() -> String {
+    DEFAULT_STAGE.into()
+}
+
+fn default_target() -> String {
+    DEFAULT_TARGET.into()
+}
+
+fn default_prerequisite() -> String {
+    DEFAULT_PREREQUISITE.into()
+}
@@ -1,33 +1,70 @@
+use postgres::error::INVALID_AUTHORIZATION_SPECIFICATION;
+use postgres::error::INVALID_CATALOG_NAME;
+use postgres::error::INVALID_PASSWORD;
+use postgres::error::QUERY_CANCELED;
 use postgres::Error as PgError;
 use std::error::Error;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
-use time::ParseError as TimeParseError;
+use std::io::ErrorKind;
 
 pub type DatabaseResult<T> = Result<T, DatabaseError>;
 
-#[derive(Debug)]
+/// Serialized with `kind` tag, so API clients get details of the error.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum DatabaseError {
     ConnectionError { message: String },
+    ConnectionRefused { message: String },
+    AuthenticationFailed { message: String },
+    DatabaseNotExists { message: String },
+    Timeout { message: String },
     QueryExecutionError { message: String },
     PrepareQueryError { message: String },
     TransactionError { message: String },
     ConversionError { message: String, column: String },
-    TimeParseError { message: String },
+    TimeParseError { column: String, value: String },
     ColumnNotExists,
+    NullValue { column: String },
+    SchemaNotExists { schema: String },
+    TableNotExists { table: String },
+    MappedColumnNotExists { table: String, column: String },
+    InvalidTimeZone { zone: String },
+    DependenciesNotMapped,
+    InvalidInterval { target: String },
+    TooManyRowErrors { limit: usize, message: String },
 }
 
 impl DatabaseError {
+    /// Splits connection errors by cause using SQLSTATE code or kind of IO error.
     pub fn connection_error(error: PgError) -> DatabaseError {
-        DatabaseError::ConnectionError {
-            message: format!("{}", error),
+        let message = format!("{}", error);
+
+        match error.code() {
+            Some(code)
+                if *code == INVALID_PASSWORD || *code == INVALID_AUTHORIZATION_SPECIFICATION =>
+            {
+                DatabaseError::AuthenticationFailed { message }
+            }
+            Some(code) if *code == INVALID_CATALOG_NAME => {
+                DatabaseError::DatabaseNotExists { message }
+            }
+            _ => match error.as_io().map(|error| error.kind()) {
+                Some(ErrorKind::ConnectionRefused) => DatabaseError::ConnectionRefused { message },
+                Some(ErrorKind::TimedOut) => DatabaseError::Timeout { message },
+                _ => DatabaseError::ConnectionError { message },
+            },
         }
     }
 
     pub fn query_execution_error(error: PgError) -> DatabaseError {
-        DatabaseError::QueryExecutionError {
-            message: format!("{}", error),
+        let message = format!("{}", error);
+
+        if error.code() == Some(&QUERY_CANCELED) {
+            DatabaseError::Timeout { message }
+        } else {
+            DatabaseError::QueryExecutionError { message }
         }
     }
 
@@ -52,19 +89,98 @@ impl DatabaseError {
         }
     }
 
-    pub fn time_parse_error(error: TimeParseError) -> DatabaseError {
-        warn!("Time parse error: {}", error);
+    pub fn unknown_time_format(column: &str, value: &str) -> DatabaseError {
+        warn!(
+            "Value `{}` of column `{}` does not match any time format",
+            value, column
+        );
 
         DatabaseError::TimeParseError {
-            message: format!("{}", error),
+            column: column.into(),
+            value: value.into(),
         }
     }
 
+    pub fn invalid_time_zone(zone: &str) -> DatabaseError {
+        warn!("Invalid time zone `{}`", zone);
+
+        DatabaseError::InvalidTimeZone { zone: zone.into() }
+    }
+
     pub fn column_not_exists() -> DatabaseError {
         warn!("Column does not exists");
 
         DatabaseError::ColumnNotExists
     }
+
+    pub fn null_value(column: &str) -> DatabaseError {
+        warn!("Column `{}` contains null value", column);
+
+        DatabaseError::NullValue {
+            column: column.into(),
+        }
+    }
+
+    pub fn schema_not_exists(schema: &str) -> DatabaseError {
+        warn!("Schema `{}` does not exists", schema);
+
+        DatabaseError::SchemaNotExists {
+            schema: schema.into(),
+        }
+    }
+
+    pub fn table_not_exists(table: &str) -> DatabaseError {
+        warn!("Table `{}` does not exists", table);
+
+        DatabaseError::TableNotExists {
+            table: table.into(),
+        }
+    }
+
+    pub fn mapped_column_not_exists(table: &str, column: &str) -> DatabaseError {
+        warn!("Column `{}` does not exists in table `{}`", column, table);
+
+        DatabaseError::MappedColumnNotExists {
+            table: table.into(),
+            column: column.into(),
+        }
+    }
+
+    pub fn dependencies_not_mapped() -> DatabaseError {
+        warn!("Dependencies are not mapped");
+
+        DatabaseError::DependenciesNotMapped
+    }
+
+    pub fn too_many_row_errors(limit: usize, error: &DatabaseError) -> DatabaseError {
+        warn!(
+            "More than {} rows can not be read, last error: {}",
+            limit, error
+        );
+
+        DatabaseError::TooManyRowErrors {
+            limit,
+            message: error.to_string(),
+        }
+    }
+
+    /// Errors caused by value of single row, other rows still can be read.
+    pub fn is_row_error(&self) -> bool {
+        matches!(
+            self,
+            DatabaseError::ConversionError { .. }
+                | DatabaseError::TimeParseError { .. }
+                | DatabaseError::NullValue { .. }
+        )
+    }
+
+    pub fn invalid_interval(target: &str) -> DatabaseError {
+        warn!("Target `{}` ends before it starts", target);
+
+        DatabaseError::InvalidInterval {
+            target: target.into(),
+        }
+    }
 }
 
 impl Error for DatabaseError {}
@@ -73,14 +189,55 @@ impl Display for DatabaseError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         match self {
             DatabaseError::ConnectionError { message } => write!(f, "{}", message),
+            DatabaseError::ConnectionRefused { message } => write!(f, "{}", message),
+            DatabaseError::AuthenticationFailed { message } => write!(f, "{}", message),
+            DatabaseError::DatabaseNotExists { message } => write!(f, "{}", message),
+            DatabaseError::Timeout { message } => write!(f, "{}", message),
             DatabaseError::QueryExecutionError { message } => write!(f, "{}", message),
             DatabaseError::PrepareQueryError { message } => write!(f, "{}", message),
             DatabaseError::TransactionError { message } => write!(f, "{}", message),
             DatabaseError::ConversionError { message, column } => {
                 write!(f, "Conversion error for column `{}`: {}", column, message)
             }
-            DatabaseError::TimeParseError { message } => write!(f, "{}", message),
+            DatabaseError::TimeParseError { column, value } => write!(
+                f,
+                "Value `{}` of column `{}` does not match any time format",
+                value, column
+            ),
             DatabaseError::ColumnNotExists => write!(f, "Column does not exists"),
+            DatabaseError::NullValue { column } => {
+                write!(f, "Column `{}` contains null value", column)
+            }
+            DatabaseError::SchemaNotExists { schema } => {
+                write!(f, "Schema `{}` does not exists", schema)
+            }
+            DatabaseError::TableNotExists { table } => {
+                write!(f, "Table `{}` does not exists", table)
+            }
+            DatabaseError::MappedColumnNotExists { table, column } => {
+                write!(
+                    f,
+                    "Column `{}` does not exists in table `{}`",
+                    column, table
+                )
+            }
+            DatabaseError::InvalidTimeZone { zone } => write!(
+                f,
+                "Invalid time zone `{}`, expected UTC, Z or fixed offset like +03:00, named zones \
+                 are not supported",
+                zone
+            ),
+            DatabaseError::DependenciesNotMapped => {
+                write!(f, "Dependencies are not mapped for this connection")
+            }
+            DatabaseError::InvalidInterval { target } => {
+                write!(f, "Target `{}` ends before it starts", target)
+            }
+            DatabaseError::TooManyRowErrors { limit, message } => write!(
+                f,
+                "More than {} rows can not be read, last error: {}",
+                limit, message
+            ),
         }
     }
 }
@@ -0,0 +1,10 @@
+use time::Timespec;
+
+/// Point event of the stage, events without thread apply to all threads.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub name: String,
+    pub time: Timespec,
+    pub group: String,
+    pub thread: Option<String>,
+}
@@ -0,0 +1,42 @@
+use super::mapping::quote_identifier;
+use super::DatabaseError;
+use super::DatabaseResult;
+use fallible_iterator::FallibleIterator;
+use postgres::Connection;
+use std::time::Duration;
+
+/// Connection subscribed to `LISTEN` channel, used to wake up on table changes instead of polling.
+/// The connection can still run queries, notifications received meanwhile are kept for `wait`.
+pub struct Listener<'a> {
+    connection: &'a Connection,
+}
+
+impl<'a> Listener<'a> {
+    pub fn new(connection: &'a Connection, channel: &str) -> DatabaseResult<Listener<'a>> {
+        connection
+            .batch_execute(&format!("listen {}", quote_identifier(channel)))
+            .map_err(DatabaseError::query_execution_error)?;
+
+        Ok(Listener { connection })
+    }
+
+    /// Waits for notification at most `timeout`. Returns `true` if any notification was received.
+    /// All pending notifications are consumed, so a burst of changes causes single wake up.
+    pub fn wait(&self, timeout: Duration) -> DatabaseResult<bool> {
+        let notifications = self.connection.notifications();
+        let received = notifications
+            .timeout_iter(timeout)
+            .next()
+            .map_err(DatabaseError::query_execution_error)?
+            .is_some();
+
+        while notifications
+            .iter()
+            .next()
+            .map_err(DatabaseError::query_execution_error)?
+            .is_some()
+        {}
+
+        Ok(received)
+    }
+}
@@ -0,0 +1,653 @@
+use super::dependency::DependencyMapping;
+use super::quality::IntervalPolicy;
+use super::schema::ColumnReport;
+use super::schema::ColumnRole;
+use super::schema::ColumnStatus;
+use super::schema::SchemaReport;
+use super::schema::TableReport;
+use super::timestamp::TimeColumn;
+use super::timestamp::TimeDecoder;
+use super::DatabaseError;
+use super::DatabaseResult;
+use super::TimeSettings;
+use postgres::Connection;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+const DEFAULT_SCHEMA: &str = "engine";
+const DEFAULT_TABLE: &str = "target";
+const DEFAULT_STAGE: &str = "maker_name";
+const DEFAULT_NAME: &str = "name";
+const DEFAULT_START_DATE: &str = "start_date";
+const DEFAULT_END_DATE: &str = "end_date";
+const DEFAULT_GROUP: &str = "target_group";
+const DEFAULT_THREAD: &str = "thread_name";
+const DEFAULT_EVENT_TIME: &str = "event_date";
+const DEFAULT_EVENT_GROUP: &str = "event_group";
+const MIN_CANDIDATE_COLUMNS: usize = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SourceMapping {
+    schema: String,
+    table: String,
+    stage: String,
+    name: String,
+    start_date: String,
+    end_date: String,
+    group: String,
+    thread: String,
+    // Scalars must precede `time` table, TOML can not emit values after tables.
+    invalid_intervals: IntervalPolicy,
+    max_row_errors: usize,
+    time: TimeSettings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dependencies: Option<DependencyMapping>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hierarchy: Option<HierarchyMapping>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    events: Option<EventMapping>,
+}
+
+/// Table in mapped schema with point events. Events with null thread are stage-wide markers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventMapping {
+    table: String,
+    #[serde(default = "default_stage")]
+    stage: String,
+    #[serde(default = "default_name")]
+    name: String,
+    #[serde(default = "default_event_time")]
+    time: String,
+    #[serde(default = "default_event_group")]
+    group: String,
+    #[serde(default = "default_thread")]
+    thread: String,
+}
+
+/// Columns identifying nested targets, `parent` contains `id` of the enclosing target or null for
+/// top-level targets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HierarchyMapping {
+    id: String,
+    parent: String,
+}
+
+impl Default for SourceMapping {
+    fn default() -> SourceMapping {
+        SourceMapping {
+            schema: DEFAULT_SCHEMA.into(),
+            table: DEFAULT_TABLE.into(),
+            stage: DEFAULT_STAGE.into(),
+            name: DEFAULT_NAME.into(),
+            start_date: DEFAULT_START_DATE.into(),
+            end_date: DEFAULT_END_DATE.into(),
+            group: DEFAULT_GROUP.into(),
+            thread: DEFAULT_THREAD.into(),
+            invalid_intervals: IntervalPolicy::default(),
+            max_row_errors: 0,
+            time: TimeSettings::default(),
+            dependencies: None,
+            hierarchy: None,
+            events: None,
+        }
+    }
+}
+
+impl SourceMapping {
+    pub fn time(&self) -> &TimeSettings {
+        &self.time
+    }
+
+    pub fn interval_policy(&self) -> IntervalPolicy {
+        self.invalid_intervals
+    }
+
+    pub fn set_interval_policy(&mut self, policy: IntervalPolicy) {
+        self.invalid_intervals = policy;
+    }
+
+    /// Number of rows which can not be decoded skipped before data request fails, 0 means that
+    /// the first such row fails request.
+    pub fn max_row_errors(&self) -> usize {
+        self.max_row_errors
+    }
+
+    pub fn set_max_row_errors(&mut self, max_row_errors: usize) {
+        self.max_row_errors = max_row_errors;
+    }
+
+    pub fn has_dependencies(&self) -> bool {
+        self.dependencies.is_some()
+    }
+
+    pub fn has_hierarchy(&self) -> bool {
+        self.hierarchy.is_some()
+    }
+
+    /// Detects how start and end times must be decoded using column types returned by `validate`.
+    pub fn time_columns(&self, columns: &HashMap<String, String>) -> (TimeColumn, TimeColumn) {
+        let column_type = |column: &str| {
+            columns
+                .get(column)
+                .map(|data_type| TimeColumn::from_data_type(data_type))
+                .unwrap_or(TimeColumn::Text)
+        };
+
+        (column_type(&self.start_date), column_type(&self.end_date))
+    }
+
+    /// Checks settings which do not require database connection.
+    pub fn check(&self) -> DatabaseResult<()> {
+        TimeDecoder::new(&self.time).map(|_| ())
+    }
+
+    /// Checks that mapped table and all mapped columns exist in the database. Returns column types
+    /// of the mapped table keyed by column name.
+    pub fn validate(&self, connection: &Connection) -> DatabaseResult<HashMap<String, String>> {
+        let columns = self.table_columns(connection, &self.table)?;
+        let hierarchy_columns = self
+            .hierarchy
+            .iter()
+            .flat_map(|hierarchy| [hierarchy.id.as_str(), hierarchy.parent.as_str()]);
+
+        for column in self.columns().iter().copied().chain(hierarchy_columns) {
+            if !columns.contains_key(column) {
+                return Err(DatabaseError::mapped_column_not_exists(
+                    &self.qualified_table(),
+                    column,
+                ));
+            }
+        }
+
+        Ok(columns)
+    }
+
+    /// Checks the mapped table and every table in the database which has at least two mapped
+    /// column names, so users can find the right table when mapping does not match.
+    pub fn inspect(&self, connection: &Connection) -> DatabaseResult<SchemaReport> {
+        let roles = self.column_roles();
+        let names: Vec<String> = roles.iter().map(|(_, column)| column.to_string()).collect();
+        let mut tables: BTreeMap<(String, String), HashMap<String, String>> = BTreeMap::new();
+
+        for row in &connection
+            .query(include_str!("sql/candidate_columns.sql"), &[&names])
+            .map_err(DatabaseError::query_execution_error)?
+        {
+            let schema: String = row
+                .get_opt(0)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "schema name"))?;
+            let table: String = row
+                .get_opt(1)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "table name"))?;
+            let name: String = row
+                .get_opt(2)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "column name"))?;
+            let data_type: String = row
+                .get_opt(3)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "data type"))?;
+
+            tables
+                .entry((schema, table))
+                .or_default()
+                .insert(name, data_type);
+        }
+
+        let mapped_key = (self.schema.clone(), self.table.clone());
+
+        if !tables.contains_key(&mapped_key) {
+            match self.table_columns(connection, &self.table) {
+                Ok(columns) => {
+                    tables.insert(mapped_key.clone(), columns);
+                }
+                Err(DatabaseError::SchemaNotExists { .. })
+                | Err(DatabaseError::TableNotExists { .. }) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        let mut reports = Vec::new();
+
+        for ((schema, table), columns) in tables {
+            let mapped = schema == mapped_key.0 && table == mapped_key.1;
+            let found = names
+                .iter()
+                .filter(|name| columns.contains_key(name.as_str()))
+                .count();
+
+            if !mapped && found < MIN_CANDIDATE_COLUMNS {
+                continue;
+            }
+
+            let columns: Vec<_> = roles
+                .iter()
+                .map(|(role, column)| {
+                    let data_type = columns.get(*column).cloned();
+
+                    ColumnReport {
+                        role: role.name,
+                        column: column.to_string(),
+                        status: ColumnStatus::of(*role, data_type.as_deref()),
+                        data_type,
+                    }
+                })
+                .collect();
+            let missing = columns
+                .iter()
+                .filter(|column| column.status == ColumnStatus::Missing)
+                .map(|column| column.column.clone())
+                .collect();
+            let (rows, error) = match row_estimate(connection, &schema, &table) {
+                Ok(rows) => (rows, None),
+                Err(error) => (None, Some(error.to_string())),
+            };
+
+            reports.push(TableReport {
+                valid: columns
+                    .iter()
+                    .all(|column| column.status == ColumnStatus::Ok),
+                schema,
+                table,
+                mapped,
+                rows,
+                error,
+                columns,
+                missing,
+            });
+        }
+
+        Ok(SchemaReport {
+            schema: self.schema.clone(),
+            table: self.table.clone(),
+            valid: reports.iter().any(|table| table.mapped && table.valid),
+            tables: reports,
+        })
+    }
+
+    /// Checks that mapped table is valid and has at least one row.
+    pub fn has_rows(&self, connection: &Connection) -> DatabaseResult<bool> {
+        self.validate(connection)?;
+
+        let sql = self.render(include_str!("sql/has_rows.sql"));
+
+        connection
+            .query(&sql, &[])
+            .map_err(DatabaseError::query_execution_error)?
+            .iter()
+            .next()
+            .ok_or_else(DatabaseError::column_not_exists)?
+            .get_opt(0)
+            .ok_or_else(DatabaseError::column_not_exists)?
+            .map_err(|error| DatabaseError::conversion_error(error, "has rows"))
+    }
+
+    /// Replaces placeholders in SQL template with quoted identifiers from this mapping. Identifier
+    /// and parent of nested targets are null when hierarchy is not mapped.
+    pub fn render(&self, template: &str) -> String {
+        self.render_with(template, Vec::new())
+    }
+
+    /// Same as `render`, also replaces time value placeholders with expressions suitable for
+    /// given column types.
+    pub fn render_data(&self, template: &str, start: TimeColumn, end: TimeColumn) -> String {
+        self.render_with(
+            template,
+            vec![
+                (
+                    "start_value",
+                    start.select_expression(&quote_identifier(&self.start_date)),
+                ),
+                (
+                    "end_value",
+                    end.select_expression(&quote_identifier(&self.end_date)),
+                ),
+            ],
+        )
+    }
+
+    /// Replaces mapping placeholders together with `values` in one pass over the template, so
+    /// placeholders inside quoted identifiers are never expanded.
+    fn render_with(&self, template: &str, mut values: Vec<(&str, String)>) -> String {
+        let (id_value, parent_value) = match &self.hierarchy {
+            Some(hierarchy) => (
+                format!("{}::text", quote_identifier(&hierarchy.id)),
+                format!("{}::text", quote_identifier(&hierarchy.parent)),
+            ),
+            None => ("null::text".into(), "null::text".into()),
+        };
+
+        values.extend(vec![
+            ("id_value", id_value),
+            ("parent_value", parent_value),
+            ("table", self.qualified_table()),
+            ("stage", quote_identifier(&self.stage)),
+            ("name", quote_identifier(&self.name)),
+            ("start_date", quote_identifier(&self.start_date)),
+            ("end_date", quote_identifier(&self.end_date)),
+            ("group", quote_identifier(&self.group)),
+            ("thread", quote_identifier(&self.thread)),
+        ]);
+
+        substitute(template, &values)
+    }
+
+    /// Checks that mapped dependency column or table exists and returns query selecting target
+    /// and prerequisite names of one stage. Column types of the mapped table are returned by
+    /// `validate`.
+    pub fn dependency_query(
+        &self,
+        connection: &Connection,
+        columns: &HashMap<String, String>,
+    ) -> DatabaseResult<String> {
+        match &self.dependencies {
+            None => Err(DatabaseError::dependencies_not_mapped()),
+            Some(DependencyMapping::Column { column, separator }) => {
+                let data_type = columns.get(column).ok_or_else(|| {
+                    DatabaseError::mapped_column_not_exists(&self.qualified_table(), column)
+                })?;
+                let prerequisites = if data_type == "ARRAY" {
+                    format!("{}::text[]", quote_identifier(column))
+                } else {
+                    format!(
+                        "string_to_array({}::text, {})",
+                        quote_identifier(column),
+                        quote_literal(separator)
+                    )
+                };
+
+                Ok(self.render_with(
+                    include_str!("sql/dependencies_column.sql"),
+                    vec![("prerequisites", prerequisites)],
+                ))
+            }
+            Some(DependencyMapping::Table {
+                table,
+                stage,
+                target,
+                prerequisite,
+            }) => {
+                let qualified_table = self.qualify(table);
+                let table_columns = self.table_columns(connection, table)?;
+
+                for column in &[stage, target, prerequisite] {
+                    if !table_columns.contains_key(column.as_str()) {
+                        return Err(DatabaseError::mapped_column_not_exists(
+                            &qualified_table,
+                            column,
+                        ));
+                    }
+                }
+
+                Ok(substitute(
+                    include_str!("sql/dependencies_table.sql"),
+                    &[
+                        ("dependency_table", qualified_table),
+                        ("dependency_stage", quote_identifier(stage)),
+                        ("dependency_target", quote_identifier(target)),
+                        ("dependency_prerequisite", quote_identifier(prerequisite)),
+                    ],
+                ))
+            }
+        }
+    }
+
+    /// Checks that mapped event table and its columns exist and returns query selecting events of
+    /// one stage together with the type of the time column. Returns `None` when events are not
+    /// mapped.
+    pub fn event_query(
+        &self,
+        connection: &Connection,
+    ) -> DatabaseResult<Option<(String, TimeColumn)>> {
+        let events = match &self.events {
+            Some(events) => events,
+            None => return Ok(None),
+        };
+        let qualified_table = self.qualify(&events.table);
+        let table_columns = self.table_columns(connection, &events.table)?;
+
+        for column in &[
+            &events.stage,
+            &events.name,
+            &events.time,
+            &events.group,
+            &events.thread,
+        ] {
+            if !table_columns.contains_key(column.as_str()) {
+                return Err(DatabaseError::mapped_column_not_exists(
+                    &qualified_table,
+                    column,
+                ));
+            }
+        }
+
+        let time_column = TimeColumn::from_data_type(&table_columns[&events.time]);
+        let sql = substitute(
+            include_str!("sql/events.sql"),
+            &[
+                (
+                    "event_time_value",
+                    time_column.select_expression(&quote_identifier(&events.time)),
+                ),
+                ("event_table", qualified_table),
+                ("event_stage", quote_identifier(&events.stage)),
+                ("event_name", quote_identifier(&events.name)),
+                ("event_time", quote_identifier(&events.time)),
+                ("event_group", quote_identifier(&events.group)),
+                ("event_thread", quote_identifier(&events.thread)),
+            ],
+        );
+
+        Ok(Some((sql, time_column)))
+    }
+
+    /// Returns column types of the table in mapped schema keyed by column name.
+    fn table_columns(
+        &self,
+        connection: &Connection,
+        table: &str,
+    ) -> DatabaseResult<HashMap<String, String>> {
+        let mut columns = HashMap::new();
+
+        for row in &connection
+            .query(include_str!("sql/columns.sql"), &[&self.schema, &table])
+            .map_err(DatabaseError::query_execution_error)?
+        {
+            let name: String = row
+                .get_opt(0)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "column name"))?;
+            let data_type: String = row
+                .get_opt(1)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "data type"))?;
+
+            columns.insert(name, data_type);
+        }
+
+        if columns.is_empty() {
+            let schema_exists: bool = connection
+                .query(include_str!("sql/schema_exists.sql"), &[&self.schema])
+                .map_err(DatabaseError::query_execution_error)?
+                .iter()
+                .next()
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .get_opt(0)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "schema exists"))?;
+
+            return Err(if schema_exists {
+                DatabaseError::table_not_exists(&self.qualify(table))
+            } else {
+                DatabaseError::schema_not_exists(&self.schema)
+            });
+        }
+
+        Ok(columns)
+    }
+
+    fn qualified_table(&self) -> String {
+        self.qualify(&self.table)
+    }
+
+    fn qualify(&self, table: &str) -> String {
+        format!(
+            "{}.{}",
+            quote_identifier(&self.schema),
+            quote_identifier(table)
+        )
+    }
+
+    /// Mapped columns of target table with their roles, including hierarchy columns.
+    fn column_roles(&self) -> Vec<(ColumnRole, &str)> {
+        let role = |name, time| ColumnRole { name, time };
+        let mut roles = vec![
+            (role("stage", false), self.stage.as_str()),
+            (role("name", false), self.name.as_str()),
+            (role("start_date", true), self.start_date.as_str()),
+            (role("end_date", true), self.end_date.as_str()),
+            (role("group", false), self.group.as_str()),
+            (role("thread", false), self.thread.as_str()),
+        ];
+
+        if let Some(hierarchy) = &self.hierarchy {
+            roles.push((role("id", false), hierarchy.id.as_str()));
+            roles.push((role("parent", false), hierarchy.parent.as_str()));
+        }
+
+        roles
+    }
+
+    fn columns(&self) -> [&str; 6] {
+        [
+            &self.stage,
+            &self.name,
+            &self.start_date,
+            &self.end_date,
+            &self.group,
+            &self.thread,
+        ]
+    }
+}
+
+fn default_stage() -> String {
+    DEFAULT_STAGE.into()
+}
+
+fn default_name() -> String {
+    DEFAULT_NAME.into()
+}
+
+fn default_event_time() -> String {
+    DEFAULT_EVENT_TIME.into()
+}
+
+fn default_event_group() -> String {
+    DEFAULT_EVENT_GROUP.into()
+}
+
+fn default_thread() -> String {
+    DEFAULT_THREAD.into()
+}
+
+/// Estimated number of rows from planner statistics, unknown until the table is analyzed.
+fn row_estimate(connection: &Connection, schema: &str, table: &str) -> DatabaseResult<Option<i64>> {
+    connection
+        .query(include_str!("sql/row_count.sql"), &[&schema, &table])
+        .map_err(DatabaseError::query_execution_error)?
+        .iter()
+        .next()
+        .map_or(Ok(None), |row| {
+            row.get_opt(0)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "row count"))
+        })
+}
+
+/// Replaces `{name}` placeholders of the template by their values. Values are not searched for
+/// placeholders, unknown placeholders are kept as is.
+fn substitute(template: &str, values: &[(&str, String)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let replacement = rest.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &rest[1..end])
+                .map(|(_, value)| (end, value))
+        });
+
+        match replacement {
+            Some((end, value)) => {
+                result.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+pub fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifiers_and_literals_are_quoted() {
+        assert_eq!(quote_identifier("name"), "\"name\"");
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+        assert_eq!(quote_literal("a,b"), "'a,b'");
+        assert_eq!(quote_literal("it's"), "'it''s'");
+    }
+
+    #[test]
+    fn placeholders_are_replaced_once() {
+        let mapping = SourceMapping {
+            table: "{stage}".into(),
+            stage: "\"; drop table x; --".into(),
+            name: "{name}".into(),
+            ..SourceMapping::default()
+        };
+
+        assert_eq!(
+            mapping.render("select {name}, {stage} from {table} where {unknown}"),
+            "select \"{name}\", \"\"\"; drop table x; --\" from \"engine\".\"{stage}\" \
+             where {unknown}"
+        );
+    }
+
+    #[test]
+    fn hierarchy_and_time_values() {
+        let mapping = SourceMapping::default();
+        let sql = mapping.render_data(
+            "{id_value}, {parent_value}, {start_value}, {end_value}",
+            TimeColumn::TimestampTz,
+            TimeColumn::Text,
+        );
+
+        assert_eq!(
+            sql,
+            "null::text, null::text, \"start_date\", \"end_date\"::text"
+        );
+    }
+}
@@ -1,14 +1,47 @@
+mod dependency;
 mod error;
+mod event;
+mod listener;
+mod mapping;
+mod quality;
+mod schema;
+mod stage;
+mod target;
+mod timestamp;
+mod trend;
 
+pub use self::dependency::Dependency;
 pub use self::error::DatabaseError;
 pub use self::error::DatabaseResult;
+pub use self::event::Event;
+pub use self::listener::Listener;
+pub use self::mapping::SourceMapping;
+pub use self::quality::IntervalPolicy;
+pub use self::quality::QualityReport;
+pub use self::schema::SchemaReport;
+pub use self::stage::Stage;
+pub use self::stage::StageOrder;
+pub use self::stage::StagePage;
+pub use self::stage::StageQuery;
+pub use self::target::Ancestor;
+pub use self::target::Target;
+pub use self::timestamp::TimeSettings;
+pub use self::trend::TrendTarget;
+
+use self::timestamp::TimeColumn;
+use self::timestamp::TimeDecoder;
 
 use fallible_iterator::FallibleIterator;
 use postgres::params::ConnectParams;
 use postgres::params::Host;
+use postgres::rows::Row;
+use postgres::transaction::Transaction;
 use postgres::Connection;
 use postgres::TlsMode;
-use time::strptime;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::convert::Infallible;
 use time::Timespec;
 
 #[derive(Debug)]
@@ -17,6 +50,9 @@ pub struct PostgreSQL {
     port: u16,
     user: String,
     password: String,
+    mapping: SourceMapping,
+    fetch_size: i32,
+    default_database: String,
 }
 
 const DEFAULT_DATABASE: &str = "postgres";
@@ -26,12 +62,42 @@ impl PostgreSQL {
     pub fn new(server: &str, port: u16, user: &str, password: &str) -> PostgreSQL {
         PostgreSQL {
             server: server.into(),
-            port: port,
+            port,
             user: user.into(),
             password: password.into(),
+            mapping: SourceMapping::default(),
+            fetch_size: FETCH_LIMIT,
+            default_database: DEFAULT_DATABASE.into(),
         }
     }
 
+    pub fn with_fetch_size(mut self, fetch_size: i32) -> PostgreSQL {
+        self.fetch_size = fetch_size;
+        self
+    }
+
+    pub fn with_default_database(mut self, database: &str) -> PostgreSQL {
+        self.default_database = database.into();
+        self
+    }
+
+    pub fn with_mapping(mut self, mapping: &SourceMapping) -> PostgreSQL {
+        self.mapping = mapping.clone();
+        self
+    }
+
+    /// Overrides number of rows which can not be decoded skipped before data request fails.
+    pub fn with_max_row_errors(mut self, max_row_errors: usize) -> PostgreSQL {
+        self.mapping.set_max_row_errors(max_row_errors);
+        self
+    }
+
+    /// Overrides interval policy of the mapping.
+    pub fn with_interval_policy(mut self, policy: IntervalPolicy) -> PostgreSQL {
+        self.mapping.set_interval_policy(policy);
+        self
+    }
+
     pub fn database_names(&self) -> DatabaseResult<Vec<String>> {
         let connection = self.connect(None)?;
         let mut result = Vec::new();
@@ -52,79 +118,540 @@ impl PostgreSQL {
         Ok(result)
     }
 
-    pub fn stage_names(&self, database: &str) -> DatabaseResult<Vec<String>> {
+    /// Checks the mapped table and finds other tables which look like target tables.
+    pub fn inspect(&self, database: &str) -> DatabaseResult<SchemaReport> {
         let connection = self.connect(Some(database))?;
-        let mut result = Vec::new();
+
+        self.mapping.inspect(&connection)
+    }
+
+    /// Checks whether the mapped table exists in the database and has any rows.
+    pub fn has_data(&self, database: &str) -> DatabaseResult<bool> {
+        let connection = self.connect(Some(database))?;
+
+        self.mapping.has_rows(&connection)
+    }
+
+    /// Lists stages with their time range and sizes, filtered, sorted and paged by `query`. Time
+    /// range of text time columns is found after decoding, so such stages are sorted and paged
+    /// here instead of the database.
+    pub fn stages(&self, database: &str, query: &StageQuery) -> DatabaseResult<StagePage> {
+        let connection = self.connect(Some(database))?;
+
+        let columns = self.mapping.validate(&connection)?;
+        let (start_column, end_column) = self.mapping.time_columns(&columns);
+        let decoder = TimeDecoder::new(self.mapping.time())?;
+        let text_times = start_column.is_text() || end_column.is_text();
+        let order = if text_times {
+            StageOrder::Name
+        } else {
+            query.order
+        };
+        let (limit, offset) = if text_times {
+            (None, 0)
+        } else {
+            (query.limit, query.offset)
+        };
+        let times = if text_times {
+            let transaction = connection
+                .transaction()
+                .map_err(DatabaseError::transaction_error)?;
+
+            Some(self.stage_times(
+                &transaction,
+                &decoder,
+                start_column,
+                end_column,
+                query.filter.as_deref(),
+            )?)
+        } else {
+            None
+        };
+        let template = include_str!("sql/stages.sql").replace("{order}", order.expression());
+        let sql = self
+            .mapping
+            .render_data(&template, start_column, end_column);
+        let mut stages = Vec::new();
 
         for row in &connection
-            .query(include_str!("sql/stages.sql"), &[])
+            .query(&sql, &[&query.filter, &limit, &offset])
             .map_err(DatabaseError::query_execution_error)?
         {
-            let name = row
+            let name: String = row
                 .get_opt(0)
-                .ok_or_else(DatabaseError::column_not_exists)?;
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "stage name"))?;
+            let (start_time, end_time) = match &times {
+                Some(times) => match times.get(&name) {
+                    Some(&times) => times,
+                    None => continue,
+                },
+                None => (
+                    decoder.decode(&row, 1, start_column, "first start time")?,
+                    decoder.decode_optional(&row, 2, end_column, "last end time")?,
+                ),
+            };
+            let count = |index: usize, column: &str| -> DatabaseResult<i64> {
+                row.get_opt(index)
+                    .ok_or_else(DatabaseError::column_not_exists)?
+                    .map_err(|error| DatabaseError::conversion_error(error, column))
+            };
+            let targets = count(3, "targets")?;
+            let groups = count(4, "groups")?;
+            let threads = count(5, "threads")?;
+            let running: bool = row
+                .get_opt(6)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "running"))?;
 
-            result
-                .push(name.map_err(|error| DatabaseError::conversion_error(error, "maker name"))?);
+            stages.push(Stage {
+                name,
+                start_time,
+                end_time,
+                targets,
+                groups,
+                threads,
+                running,
+            });
+        }
+
+        // Stages without decodable times are skipped on text time path, so only returned stages
+        // are counted there.
+        let total = if text_times {
+            let total = stages.len() as i64;
+
+            if query.order == StageOrder::Recent {
+                stages.sort_by_key(|stage| Reverse(stage.start_time));
+            }
+
+            stages = stages
+                .into_iter()
+                .skip(query.offset as usize)
+                .take(query.limit.map_or(usize::MAX, |limit| limit as usize))
+                .collect();
+
+            total
+        } else {
+            self.stage_count(
+                &connection,
+                query.filter.as_deref(),
+                start_column,
+                end_column,
+            )?
+        };
+
+        Ok(StagePage { stages, total })
+    }
+
+    fn stage_count(
+        &self,
+        connection: &Connection,
+        filter: Option<&str>,
+        start_column: TimeColumn,
+        end_column: TimeColumn,
+    ) -> DatabaseResult<i64> {
+        let sql = self.mapping.render_data(
+            include_str!("sql/stage_count.sql"),
+            start_column,
+            end_column,
+        );
+
+        connection
+            .query(&sql, &[&filter])
+            .map_err(DatabaseError::query_execution_error)?
+            .iter()
+            .next()
+            .ok_or_else(DatabaseError::column_not_exists)?
+            .get_opt(0)
+            .ok_or_else(DatabaseError::column_not_exists)?
+            .map_err(|error| DatabaseError::conversion_error(error, "stage count"))
+    }
+
+    /// Reads first start and last end time of every stage matching `filter`. Rows with times which
+    /// can not be decoded are skipped, they are reported by data quality checks.
+    fn stage_times(
+        &self,
+        transaction: &Transaction,
+        decoder: &TimeDecoder,
+        start_column: TimeColumn,
+        end_column: TimeColumn,
+        filter: Option<&str>,
+    ) -> DatabaseResult<HashMap<String, (Timespec, Option<Timespec>)>> {
+        let statement = transaction
+            .prepare(&self.mapping.render_data(
+                include_str!("sql/stage_times.sql"),
+                start_column,
+                end_column,
+            ))
+            .map_err(DatabaseError::prepare_query_error)?;
+        let mut rows = statement
+            .lazy_query(transaction, &[&filter], self.fetch_size)
+            .map_err(DatabaseError::query_execution_error)?;
+        let mut result: HashMap<String, (Timespec, Option<Timespec>)> = HashMap::new();
+
+        while let Some(row) = rows.next().map_err(DatabaseError::query_execution_error)? {
+            let name: String = row
+                .get_opt(0)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "stage name"))?;
+            let (start_time, end_time) = match (
+                decoder.decode(&row, 1, start_column, "start time"),
+                decoder.decode_optional(&row, 2, end_column, "end time"),
+            ) {
+                (Ok(start_time), Ok(end_time)) => (start_time, end_time),
+                _ => continue,
+            };
+            let times = result.entry(name).or_insert((start_time, end_time));
+
+            times.0 = times.0.min(start_time);
+            times.1 = times.1.max(end_time);
         }
 
         Ok(result)
     }
 
+    /// Calls `callback` for every target of the stage with interval fixed by interval policy.
+    /// Returns data quality report of the stage.
     pub fn data<F, E>(
         &self,
         database: &str,
         stage: &str,
+        callback: F,
+    ) -> DatabaseResult<Result<QualityReport, E>>
+    where
+        F: FnMut(&Target) -> Result<(), E>,
+    {
+        let connection = self.connection(database)?;
+
+        self.data_since(&connection, stage, None, callback)
+            .map(|result| result.map(|(_, report)| report))
+    }
+
+    /// Calls `callback` only for running targets and targets inserted or updated by transactions
+    /// not finished before the snapshot of the previous call, `since` is the cursor returned by
+    /// that call. The cursor is the oldest transaction running at the snapshot, so it grows in
+    /// commit order and targets committed later are never missed, while some may be sent twice.
+    /// Returns the cursor for the next call and data quality report of returned targets.
+    pub fn data_since<F, E>(
+        &self,
+        connection: &Connection,
+        stage: &str,
+        since: Option<i64>,
         mut callback: F,
-    ) -> DatabaseResult<Result<(), E>>
+    ) -> DatabaseResult<Result<(i64, QualityReport), E>>
     where
-        F: FnMut(&str, Timespec, Timespec, &str, &str) -> Result<(), E>,
+        F: FnMut(&Target) -> Result<(), E>,
     {
-        let connection = self.connect(Some(database))?;
+        let columns = self.mapping.validate(connection)?;
+        let (start_column, end_column) = self.mapping.time_columns(&columns);
+        let decoder = TimeDecoder::new(self.mapping.time())?;
         let statement = connection
-            .prepare(include_str!("sql/data.sql"))
+            .prepare(&self.mapping.render_data(
+                include_str!("sql/data.sql"),
+                start_column,
+                end_column,
+            ))
             .map_err(DatabaseError::prepare_query_error)?;
         let transaction = connection
             .transaction()
             .map_err(DatabaseError::transaction_error)?;
+        let (snapshot_time, cursor) = self.snapshot(&transaction)?;
+        let policy = self.mapping.interval_policy();
+        let mut report = QualityReport::new(policy);
+        let mut position = 0;
         let mut rows = statement
-            .lazy_query(&transaction, &[&stage], FETCH_LIMIT)
+            .lazy_query(&transaction, &[&stage, &since], self.fetch_size)
             .map_err(DatabaseError::query_execution_error)?;
 
         while let Some(row) = rows.next().map_err(DatabaseError::query_execution_error)? {
-            let name: String = row
+            position += 1;
+
+            let mut target =
+                match decode_target(&row, &decoder, start_column, end_column, snapshot_time) {
+                    Ok(target) => target,
+                    Err(error) => {
+                        report.add_error(position, error, self.mapping.max_row_errors())?;
+                        continue;
+                    }
+                };
+
+            if !policy.apply(&mut target, &mut report)? {
+                continue;
+            }
+
+            if let Err(err) = callback(&target) {
+                return Ok(Err(err));
+            }
+        }
+
+        Ok(Ok((cursor, report)))
+    }
+
+    /// Reads targets with identifiers `ids` and all their ancestors, the most distant ancestors
+    /// first. Returns nothing when hierarchy is not mapped.
+    pub fn ancestors(
+        &self,
+        connection: &Connection,
+        stage: &str,
+        ids: &[String],
+    ) -> DatabaseResult<Vec<Ancestor>> {
+        if !self.mapping.has_hierarchy() || ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        self.mapping.validate(connection)?;
+
+        let sql = self.mapping.render(include_str!("sql/ancestors.sql"));
+        let mut result = Vec::new();
+
+        for row in &connection
+            .query(&sql, &[&stage, &ids])
+            .map_err(DatabaseError::query_execution_error)?
+        {
+            let id: String = row
                 .get_opt(0)
                 .ok_or_else(DatabaseError::column_not_exists)?
-                .map_err(|error| DatabaseError::conversion_error(error, "name"))?;
-            let start_time_str: String = row
+                .map_err(|error| DatabaseError::conversion_error(error, "target id"))?;
+            let parent: Option<String> = row
                 .get_opt(1)
                 .ok_or_else(DatabaseError::column_not_exists)?
-                .map_err(|error| DatabaseError::conversion_error(error, "start tame"))?;
-            let end_time_str: String = row
+                .map_err(|error| DatabaseError::conversion_error(error, "parent id"))?;
+            let thread: String = row
                 .get_opt(2)
                 .ok_or_else(DatabaseError::column_not_exists)?
-                .map_err(|error| DatabaseError::conversion_error(error, "end time"))?;
+                .map_err(|error| DatabaseError::conversion_error(error, "thread name"))?;
+
+            result.push(Ancestor { id, parent, thread });
+        }
+
+        Ok(result)
+    }
+
+    /// Reads all targets of the stage only to build data quality report.
+    pub fn quality(&self, database: &str, stage: &str) -> DatabaseResult<QualityReport> {
+        self.data(database, stage, |_| Ok::<(), Infallible>(()))
+            .map(|result| result.unwrap_or_else(|never| match never {}))
+    }
+
+    pub fn has_dependencies(&self) -> bool {
+        self.mapping.has_dependencies()
+    }
+
+    /// Reads edges between targets of the stage from mapped dependency column or table.
+    pub fn dependencies(&self, database: &str, stage: &str) -> DatabaseResult<Vec<Dependency>> {
+        let connection = self.connect(Some(database))?;
+
+        let columns = self.mapping.validate(&connection)?;
+        let sql = self.mapping.dependency_query(&connection, &columns)?;
+        let mut result = Vec::new();
+
+        for row in &connection
+            .query(&sql, &[&stage])
+            .map_err(DatabaseError::query_execution_error)?
+        {
+            let target: String = row
+                .get_opt(0)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "target name"))?;
+            let prerequisite: String = row
+                .get_opt(1)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "prerequisite name"))?;
+
+            result.push(Dependency {
+                target,
+                prerequisite,
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// Reads point events of the stage from mapped event table, returns nothing when events are
+    /// not mapped.
+    pub fn events(&self, database: &str, stage: &str) -> DatabaseResult<Vec<Event>> {
+        let connection = self.connect(Some(database))?;
+        let (sql, time_column) = match self.mapping.event_query(&connection)? {
+            Some(query) => query,
+            None => return Ok(Vec::new()),
+        };
+        let decoder = TimeDecoder::new(self.mapping.time())?;
+        let mut result = Vec::new();
+
+        for row in &connection
+            .query(&sql, &[&stage])
+            .map_err(DatabaseError::query_execution_error)?
+        {
+            let name: String = row
+                .get_opt(0)
+                .ok_or_else(DatabaseError::column_not_exists)?
+                .map_err(|error| DatabaseError::conversion_error(error, "event name"))?;
+            let time = decoder.decode(&row, 1, time_column, "event time")?;
             let group: String = row
-                .get_opt(3)
+                .get_opt(2)
                 .ok_or_else(DatabaseError::column_not_exists)?
-                .map_err(|error| DatabaseError::conversion_error(error, "group name"))?;
-            let thread: String = row
-                .get_opt(4)
+                .map_err(|error| DatabaseError::conversion_error(error, "event group"))?;
+            let thread: Option<String> = row
+                .get_opt(3)
                 .ok_or_else(DatabaseError::column_not_exists)?
-                .map_err(|error| DatabaseError::conversion_error(error, "thread name"))?;
-            let start_time = strptime(&start_time_str, "%Y-%m-%d %H:%M:%S,%f")
-                .map_err(DatabaseError::time_parse_error)?
-                .to_timespec();
-            let end_time = strptime(&end_time_str, "%Y-%m-%d %H:%M:%S,%f")
-                .map_err(DatabaseError::time_parse_error)?
-                .to_timespec();
-
-            if let Err(err) = callback(&name, start_time, end_time, &group, &thread) {
-                return Ok(Err(err));
+                .map_err(|error| DatabaseError::conversion_error(error, "event thread"))?;
+
+            result.push(Event {
+                name,
+                time,
+                group,
+                thread,
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// Reads targets matching name pattern (SQL `like`) and group of the last `limit` stages which
+    /// contain such targets. Stages are ordered by their first start time, for text time columns
+    /// the last stages are selected after decoding. Targets are decoded and fixed by interval
+    /// policy the same way as stage data, the report covers targets of all read stages.
+    pub fn trend(
+        &self,
+        database: &str,
+        name_pattern: Option<&str>,
+        group: Option<&str>,
+        limit: i64,
+    ) -> DatabaseResult<(Vec<TrendTarget>, QualityReport)> {
+        let connection = self.connect(Some(database))?;
+
+        let columns = self.mapping.validate(&connection)?;
+        let (start_column, end_column) = self.mapping.time_columns(&columns);
+        let decoder = TimeDecoder::new(self.mapping.time())?;
+        let statement = connection
+            .prepare(&self.mapping.render_data(
+                include_str!("sql/trend.sql"),
+                start_column,
+                end_column,
+            ))
+            .map_err(DatabaseError::prepare_query_error)?;
+        let transaction = connection
+            .transaction()
+            .map_err(DatabaseError::transaction_error)?;
+        let snapshot_time = self.snapshot_time(&transaction)?;
+        let text_times = start_column.is_text() || end_column.is_text();
+        let stage_starts = if text_times {
+            self.stage_times(&transaction, &decoder, start_column, end_column, None)?
+        } else {
+            HashMap::new()
+        };
+        let stage_limit = if text_times { None } else { Some(limit) };
+        let policy = self.mapping.interval_policy();
+        let mut report = QualityReport::new(policy);
+        let mut position = 0;
+        let mut rows = statement
+            .lazy_query(
+                &transaction,
+                &[&name_pattern, &group, &stage_limit],
+                self.fetch_size,
+            )
+            .map_err(DatabaseError::query_execution_error)?;
+        let mut result = Vec::new();
+
+        while let Some(row) = rows.next().map_err(DatabaseError::query_execution_error)? {
+            position += 1;
+
+            let decoded = decode_target(&row, &decoder, start_column, end_column, snapshot_time)
+                .and_then(|target| {
+                    let stage: String = row
+                        .get_opt(8)
+                        .ok_or_else(DatabaseError::column_not_exists)?
+                        .map_err(|error| DatabaseError::conversion_error(error, "stage name"))?;
+                    let stage_start = match stage_starts.get(&stage) {
+                        Some((stage_start, _)) => *stage_start,
+                        None => decoder.decode(&row, 9, start_column, "stage start time")?,
+                    };
+
+                    Ok(TrendTarget {
+                        stage,
+                        stage_start,
+                        target,
+                    })
+                });
+            let mut trend_target = match decoded {
+                Ok(trend_target) => trend_target,
+                Err(error) => {
+                    report.add_error(position, error, self.mapping.max_row_errors())?;
+                    continue;
+                }
+            };
+
+            if policy.apply(&mut trend_target.target, &mut report)? {
+                result.push(trend_target);
             }
         }
 
-        Ok(Ok(()))
+        if text_times {
+            let mut stages: Vec<(Timespec, &str)> = result
+                .iter()
+                .map(|target| (target.stage_start, target.stage.as_str()))
+                .collect();
+
+            stages.sort_unstable();
+            stages.dedup();
+
+            let first = stages.len().saturating_sub(limit as usize);
+            let selected: HashSet<String> = stages[first..]
+                .iter()
+                .map(|(_, stage)| stage.to_string())
+                .collect();
+
+            result.retain(|target| selected.contains(&target.stage));
+            result.sort_by(|a, b| {
+                (a.stage_start, &a.stage, a.target.start_time).cmp(&(
+                    b.stage_start,
+                    &b.stage,
+                    b.target.start_time,
+                ))
+            });
+        }
+
+        Ok((result, report))
+    }
+
+    /// Opens connection to `database`, so that repeated calls of `data_since` and `ancestors`
+    /// and `Listener` can share it.
+    pub fn connection(&self, database: &str) -> DatabaseResult<Connection> {
+        self.connect(Some(database))
+    }
+
+    fn snapshot_time(&self, transaction: &Transaction) -> DatabaseResult<Timespec> {
+        let rows = transaction
+            .query(include_str!("sql/now.sql"), &[])
+            .map_err(DatabaseError::query_execution_error)?;
+
+        rows.iter()
+            .next()
+            .ok_or_else(DatabaseError::column_not_exists)?
+            .get_opt(0)
+            .ok_or_else(DatabaseError::column_not_exists)?
+            .map_err(|error| DatabaseError::conversion_error(error, "snapshot time"))
+    }
+
+    /// Returns snapshot time and the oldest transaction running at the snapshot.
+    fn snapshot(&self, transaction: &Transaction) -> DatabaseResult<(Timespec, i64)> {
+        let rows = transaction
+            .query(include_str!("sql/snapshot.sql"), &[])
+            .map_err(DatabaseError::query_execution_error)?;
+        let row = rows
+            .iter()
+            .next()
+            .ok_or_else(DatabaseError::column_not_exists)?;
+        let time = row
+            .get_opt(0)
+            .ok_or_else(DatabaseError::column_not_exists)?
+            .map_err(|error| DatabaseError::conversion_error(error, "snapshot time"))?;
+        let transaction_id = row
+            .get_opt(1)
+            .ok_or_else(DatabaseError::column_not_exists)?
+            .map_err(|error| DatabaseError::conversion_error(error, "snapshot transaction"))?;
+
+        Ok((time, transaction_id))
     }
 
     fn connect(&self, database: Option<&str>) -> DatabaseResult<Connection> {
@@ -132,9 +659,57 @@ impl PostgreSQL {
         let params = ConnectParams::builder()
             .port(self.port)
             .user(&self.user, password)
-            .database(database.unwrap_or(DEFAULT_DATABASE))
+            .database(database.unwrap_or(&self.default_database))
             .build(Host::Tcp(self.server.clone()));
 
         Connection::connect(params, TlsMode::None).map_err(DatabaseError::connection_error)
     }
 }
+
+/// Decodes one row of stage data. Running targets have no end time yet, so they end at the moment
+/// of snapshot.
+fn decode_target(
+    row: &Row,
+    decoder: &TimeDecoder,
+    start_column: TimeColumn,
+    end_column: TimeColumn,
+    snapshot_time: Timespec,
+) -> DatabaseResult<Target> {
+    let name: String = row
+        .get_opt(0)
+        .ok_or_else(DatabaseError::column_not_exists)?
+        .map_err(|error| DatabaseError::conversion_error(error, "name"))?;
+    let start_time = decoder.decode(row, 1, start_column, "start time")?;
+    let end_time = decoder.decode_optional(row, 2, end_column, "end time")?;
+    let group: String = row
+        .get_opt(3)
+        .ok_or_else(DatabaseError::column_not_exists)?
+        .map_err(|error| DatabaseError::conversion_error(error, "group name"))?;
+    let thread: String = row
+        .get_opt(4)
+        .ok_or_else(DatabaseError::column_not_exists)?
+        .map_err(|error| DatabaseError::conversion_error(error, "thread name"))?;
+    let running: bool = row
+        .get_opt(5)
+        .ok_or_else(DatabaseError::column_not_exists)?
+        .map_err(|error| DatabaseError::conversion_error(error, "running"))?;
+    let id: Option<String> = row
+        .get_opt(6)
+        .ok_or_else(DatabaseError::column_not_exists)?
+        .map_err(|error| DatabaseError::conversion_error(error, "target id"))?;
+    let parent: Option<String> = row
+        .get_opt(7)
+        .ok_or_else(DatabaseError::column_not_exists)?
+        .map_err(|error| DatabaseError::conversion_error(error, "parent id"))?;
+
+    Ok(Target {
+        name,
+        start_time,
+        end_time: end_time.unwrap_or(snapshot_time),
+        group,
+        thread,
+        running,
+        id,
+        parent,
+    })
+}
@@ -0,0 +1,313 @@
+use super::DatabaseError;
+use super::DatabaseResult;
+use super::Target;
+use std::convert::TryFrom;
+use std::str::FromStr;
+use time::Duration;
+
+const MAX_SAMPLES: usize = 10;
+
+/// What to do with targets which end before they start. `Clamp` keeps the historical behavior
+/// and makes such target 1 ms long, `Milestone` makes it a point in time at its start. Names are
+/// defined only by `name`, parsing and serialization use it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "&'static str")]
+pub enum IntervalPolicy {
+    #[default]
+    Clamp,
+    Drop,
+    Swap,
+    Milestone,
+    Fail,
+}
+
+const POLICIES: [IntervalPolicy; 5] = [
+    IntervalPolicy::Clamp,
+    IntervalPolicy::Drop,
+    IntervalPolicy::Swap,
+    IntervalPolicy::Milestone,
+    IntervalPolicy::Fail,
+];
+
+impl IntervalPolicy {
+    pub fn name(self) -> &'static str {
+        match self {
+            IntervalPolicy::Clamp => "clamp",
+            IntervalPolicy::Drop => "drop",
+            IntervalPolicy::Swap => "swap",
+            IntervalPolicy::Milestone => "milestone",
+            IntervalPolicy::Fail => "fail",
+        }
+    }
+
+    /// Fixes interval of the target according to policy and counts it in `report`. Returns
+    /// `false` when target must be dropped.
+    pub fn apply(self, target: &mut Target, report: &mut QualityReport) -> DatabaseResult<bool> {
+        report.rows += 1;
+
+        if target.start_time <= target.end_time {
+            return Ok(true);
+        }
+
+        let keep = match self {
+            IntervalPolicy::Clamp => {
+                report.clamped += 1;
+                target.end_time = target.start_time + Duration::milliseconds(1);
+                true
+            }
+            IntervalPolicy::Drop => {
+                report.dropped += 1;
+                false
+            }
+            IntervalPolicy::Swap => {
+                report.swapped += 1;
+                std::mem::swap(&mut target.start_time, &mut target.end_time);
+                true
+            }
+            IntervalPolicy::Milestone => {
+                report.milestones += 1;
+                target.end_time = target.start_time;
+                true
+            }
+            IntervalPolicy::Fail => return Err(DatabaseError::invalid_interval(&target.name)),
+        };
+
+        report.add_sample(&target.name);
+
+        Ok(keep)
+    }
+}
+
+impl FromStr for IntervalPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<IntervalPolicy, String> {
+        POLICIES
+            .iter()
+            .copied()
+            .find(|policy| policy.name() == value)
+            .ok_or_else(|| {
+                let names: Vec<&str> = POLICIES.iter().map(|policy| policy.name()).collect();
+
+                format!(
+                    "Unknown interval policy `{}`, expected {}",
+                    value,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl TryFrom<String> for IntervalPolicy {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<IntervalPolicy, String> {
+        value.parse()
+    }
+}
+
+impl From<IntervalPolicy> for &'static str {
+    fn from(policy: IntervalPolicy) -> &'static str {
+        policy.name()
+    }
+}
+
+/// Data quality of one stage download: number of read rows, number of rows changed or dropped by
+/// interval policy, names of the first invalid targets and rows skipped because they can not be
+/// decoded.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QualityReport {
+    pub policy: IntervalPolicy,
+    pub rows: usize,
+    pub clamped: usize,
+    pub dropped: usize,
+    pub swapped: usize,
+    pub milestones: usize,
+    pub samples: Vec<String>,
+    pub unparsable: usize,
+    pub errors: Vec<RowError>,
+}
+
+/// Row which can not be decoded. Position is 1-based index of the row in stage data ordered by
+/// start time, value is set for values not matching any time format.
+#[derive(Debug, Clone, Serialize)]
+pub struct RowError {
+    pub position: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub message: String,
+}
+
+impl QualityReport {
+    pub fn new(policy: IntervalPolicy) -> QualityReport {
+        QualityReport {
+            policy,
+            ..QualityReport::default()
+        }
+    }
+
+    /// Number of rows with invalid interval.
+    pub fn invalid(&self) -> usize {
+        self.clamped + self.dropped + self.swapped + self.milestones
+    }
+
+    /// Skips row which can not be decoded. Fails with the row error when `max_row_errors` is 0,
+    /// with the number of errors when it is exceeded and with any error not caused by row value.
+    pub fn add_error(
+        &mut self,
+        position: usize,
+        error: DatabaseError,
+        max_row_errors: usize,
+    ) -> DatabaseResult<()> {
+        if !error.is_row_error() || max_row_errors == 0 {
+            return Err(error);
+        }
+
+        self.unparsable += 1;
+
+        if self.unparsable > max_row_errors {
+            return Err(DatabaseError::too_many_row_errors(max_row_errors, &error));
+        }
+
+        let (column, value) = match &error {
+            DatabaseError::ConversionError { column, .. } | DatabaseError::NullValue { column } => {
+                (Some(column.clone()), None)
+            }
+            DatabaseError::TimeParseError { column, value } => {
+                (Some(column.clone()), Some(value.clone()))
+            }
+            _ => (None, None),
+        };
+
+        self.errors.push(RowError {
+            position,
+            column,
+            value,
+            message: error.to_string(),
+        });
+
+        Ok(())
+    }
+
+    fn add_sample(&mut self, name: &str) {
+        if self.samples.len() < MAX_SAMPLES {
+            self.samples.push(name.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Timespec;
+
+    fn target(name: &str, start: i64, end: i64) -> Target {
+        Target {
+            name: name.into(),
+            start_time: Timespec::new(start, 0),
+            end_time: Timespec::new(end, 0),
+            group: "g".into(),
+            thread: "t".into(),
+            running: false,
+            id: None,
+            parent: None,
+        }
+    }
+
+    #[test]
+    fn inverted_intervals_are_fixed_by_policy() {
+        let millisecond = Duration::milliseconds(1);
+        let cases = [
+            (
+                IntervalPolicy::Clamp,
+                Some((20, Timespec::new(20, 0) + millisecond)),
+            ),
+            (IntervalPolicy::Drop, None),
+            (IntervalPolicy::Swap, Some((10, Timespec::new(20, 0)))),
+            (IntervalPolicy::Milestone, Some((20, Timespec::new(20, 0)))),
+        ];
+
+        for &(policy, expected) in &cases {
+            let mut target = target("late", 20, 10);
+            let mut report = QualityReport::new(policy);
+            let keep = policy.apply(&mut target, &mut report).unwrap();
+
+            assert_eq!(
+                keep.then_some((target.start_time.sec, target.end_time)),
+                expected,
+                "{}",
+                policy.name()
+            );
+            assert_eq!(report.rows, 1);
+            assert_eq!(report.invalid(), 1);
+            assert_eq!(report.samples, vec!["late".to_string()]);
+        }
+    }
+
+    #[test]
+    fn fail_policy_returns_error() {
+        let mut report = QualityReport::new(IntervalPolicy::Fail);
+        let result = IntervalPolicy::Fail.apply(&mut target("late", 20, 10), &mut report);
+
+        assert!(matches!(
+            result,
+            Err(DatabaseError::InvalidInterval { ref target }) if target == "late"
+        ));
+    }
+
+    #[test]
+    fn valid_intervals_are_kept() {
+        for &policy in &POLICIES {
+            let mut report = QualityReport::new(policy);
+
+            for &(start, end) in &[(10, 20), (10, 10)] {
+                let mut valid = target("ok", start, end);
+
+                assert!(policy.apply(&mut valid, &mut report).unwrap());
+                assert_eq!(valid.start_time, Timespec::new(start, 0));
+                assert_eq!(valid.end_time, Timespec::new(end, 0));
+            }
+
+            assert_eq!(report.rows, 2);
+            assert_eq!(report.invalid(), 0);
+            assert!(report.samples.is_empty());
+        }
+    }
+
+    #[test]
+    fn samples_are_limited() {
+        let mut report = QualityReport::new(IntervalPolicy::Drop);
+
+        for index in 0..MAX_SAMPLES + 5 {
+            let mut late = target(&format!("late-{}", index), 20, 10);
+
+            IntervalPolicy::Drop.apply(&mut late, &mut report).unwrap();
+        }
+
+        assert_eq!(report.dropped, MAX_SAMPLES + 5);
+        assert_eq!(report.samples.len(), MAX_SAMPLES);
+        assert_eq!(
+            report.samples.last().unwrap(),
+            &format!("late-{}", MAX_SAMPLES - 1)
+        );
+    }
+
+    #[test]
+    fn names_are_parsed_and_serialized() {
+        for &policy in &POLICIES {
+            let json = format!("\"{}\"", policy.name());
+
+            assert_eq!(policy.name().parse(), Ok(policy));
+            assert_eq!(serde_json::to_string(&policy).unwrap(), json);
+            assert_eq!(
+                serde_json::from_str::<IntervalPolicy>(&json).unwrap(),
+                policy
+            );
+        }
+
+        assert!("Clamp".parse::<IntervalPolicy>().is_err());
+        assert!(serde_json::from_str::<IntervalPolicy>("\"ignore\"").is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use super::timestamp::TimeColumn;
+
+/// Result of database inspection: whether the mapped table can be read and all tables which
+/// look like target tables, i.e. have at least two mapped column names, and the mapped table.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaReport {
+    pub schema: String,
+    pub table: String,
+    pub valid: bool,
+    pub tables: Vec<TableReport>,
+}
+
+/// Mapped columns of candidate table, table is valid when no column is missing or incompatible.
+/// Number of rows is an estimate, `error` is set when it could not be read.
+#[derive(Debug, Clone, Serialize)]
+pub struct TableReport {
+    pub schema: String,
+    pub table: String,
+    pub mapped: bool,
+    pub valid: bool,
+    pub rows: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub columns: Vec<ColumnReport>,
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnReport {
+    pub role: &'static str,
+    pub column: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<String>,
+    pub status: ColumnStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnStatus {
+    Ok,
+    Missing,
+    Incompatible,
+}
+
+/// Role of mapped column, time columns must have time, number or text type.
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnRole {
+    pub name: &'static str,
+    pub time: bool,
+}
+
+impl ColumnStatus {
+    pub fn of(role: ColumnRole, data_type: Option<&str>) -> ColumnStatus {
+        match data_type {
+            None => ColumnStatus::Missing,
+            Some(data_type) if role.time && !is_time_type(data_type) => ColumnStatus::Incompatible,
+            Some(_) => ColumnStatus::Ok,
+        }
+    }
+}
+
+fn is_time_type(data_type: &str) -> bool {
+    match TimeColumn::from_data_type(data_type) {
+        TimeColumn::Text => matches!(data_type, "text" | "character varying" | "character"),
+        _ => true,
+    }
+}
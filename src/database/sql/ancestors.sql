@@ -0,0 +1,26 @@
+with recursive ancestors(ancestor_id, ancestor_parent, ancestor_thread, ancestor_level) as (
+	select
+		{id_value},
+		{parent_value},
+		coalesce({thread}::text, '~'),
+		0
+	from {table}
+	where {stage}::text = $1
+		and {id_value} = any($2)
+	union all
+	select
+		{id_value},
+		{parent_value},
+		coalesce({thread}::text, '~'),
+		ancestor_level + 1
+	from {table}
+	join ancestors on {id_value} = ancestor_parent
+	where {stage}::text = $1
+		and ancestor_level < 100
+)
+select
+	ancestor_id,
+	ancestor_parent,
+	ancestor_thread
+from ancestors
+order by ancestor_level desc
@@ -0,0 +1,10 @@
+select
+	table_schema::text,
+	table_name::text,
+	column_name::text,
+	data_type::text
+from information_schema.columns
+where table_schema not in ('pg_catalog', 'information_schema')
+	and table_schema not like 'pg_toast%'
+	and column_name::text = any($1)
+order by table_schema, table_name
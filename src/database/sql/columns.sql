@@ -0,0 +1,6 @@
+select
+	column_name::text,
+	data_type::text
+from information_schema.columns
+where table_schema = $1
+	and table_name = $2
@@ -1,11 +1,18 @@
 select
-	name,
-	start_date,
-	end_date,
-	target_group,
-	coalesce(thread_name, '~') as thread_name
-from engine.target
-where maker_name = $1
-	and start_date is not null
-	and end_date is not null
-order by start_date
+	{name}::text,
+	{start_value},
+	{end_value},
+	{group}::text,
+	coalesce({thread}::text, '~') as thread_name,
+	{end_date} is null as running,
+	{id_value} as target_id,
+	{parent_value} as parent_id
+from {table}
+where {stage}::text = $1
+	and {start_date} is not null
+	and (
+		$2::bigint is null
+		or {end_date} is null
+		or ({table}.xmin::text::bigint - $2::bigint % 4294967296 + 4294967296) % 4294967296 < 2147483648
+	)
+order by {start_date}, {end_date} desc, {id_value}
@@ -0,0 +1,8 @@
+select distinct
+	{name}::text,
+	btrim(prerequisite)
+from {table}
+	cross join lateral unnest({prerequisites}) as prerequisite
+where {stage}::text = $1
+	and {start_date} is not null
+	and btrim(prerequisite) <> ''
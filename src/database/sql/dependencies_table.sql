@@ -0,0 +1,7 @@
+select distinct
+	{dependency_target}::text,
+	{dependency_prerequisite}::text
+from {dependency_table}
+where {dependency_stage}::text = $1
+	and {dependency_target} is not null
+	and {dependency_prerequisite} is not null
@@ -0,0 +1,9 @@
+select
+	{event_name}::text,
+	{event_time_value},
+	coalesce({event_group}::text, ''),
+	{event_thread}::text
+from {event_table}
+where {event_stage}::text = $1
+	and {event_time} is not null
+order by {event_time}
@@ -0,0 +1,6 @@
+select
+	case when pg_class.reltuples < 0 then null else pg_class.reltuples::bigint end
+from pg_class
+	inner join pg_namespace on pg_namespace.oid = pg_class.relnamespace
+where pg_namespace.nspname = $1
+	and pg_class.relname = $2
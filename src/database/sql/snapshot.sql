@@ -0,0 +1,3 @@
+select
+	now(),
+	txid_snapshot_xmin(txid_current_snapshot())
@@ -0,0 +1,4 @@
+select count(distinct {stage})
+from {table}
+where {start_date} is not null
+    and ($1::text is null or strpos(lower({stage}::text), lower($1)) > 0)
@@ -0,0 +1,7 @@
+select
+	{stage}::text,
+	{start_value},
+	{end_value}
+from {table}
+where {start_date} is not null
+	and ($1::text is null or strpos(lower({stage}::text), lower($1)) > 0)
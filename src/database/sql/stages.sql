@@ -1,4 +1,15 @@
-select distinct
-    maker_name
-from engine.target
-order by maker_name
+select
+    {stage}::text as stage_name,
+    min({start_value}) as first_start,
+    max({end_value}) as last_end,
+    count(*) as targets,
+    count(distinct {group}) as groups,
+    count(distinct {thread}) as threads,
+    bool_or({end_date} is null) as running
+from {table}
+where {start_date} is not null
+    and ($1::text is null or strpos(lower({stage}::text), lower($1)) > 0)
+group by 1
+order by {order}
+limit $2
+offset $3
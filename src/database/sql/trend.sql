@@ -0,0 +1,33 @@
+with trend_stages as (
+	select
+		{stage}::text as trend_stage,
+		min({start_value}) as trend_stage_start
+	from {table}
+	where {start_date} is not null
+		and {stage}::text in (
+			select {stage}::text
+			from {table}
+			where ($1::text is null or {name}::text like $1)
+				and ($2::text is null or {group}::text = $2)
+		)
+	group by 1
+	order by 2 desc
+	limit $3
+)
+select
+	{name}::text,
+	{start_value},
+	{end_value},
+	{group}::text,
+	coalesce({thread}::text, '~') as thread_name,
+	{end_date} is null as running,
+	{id_value} as target_id,
+	{parent_value} as parent_id,
+	trend_stages.trend_stage,
+	trend_stages.trend_stage_start
+from {table}
+	inner join trend_stages on trend_stages.trend_stage = {stage}::text
+where {start_date} is not null
+	and ($1::text is null or {name}::text like $1)
+	and ($2::text is null or {group}::text = $2)
+order by trend_stages.trend_stage_start, trend_stages.trend_stage, {start_date}
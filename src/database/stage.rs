@@ -0,0 +1,47 @@
+use time::Timespec;
+
+/// Stage summary returned by stage list query. End time is missing when no target finished yet.
+#[derive(Debug, Clone)]
+pub struct Stage {
+    pub name: String,
+    pub start_time: Timespec,
+    pub end_time: Option<Timespec>,
+    pub targets: i64,
+    pub groups: i64,
+    pub threads: i64,
+    pub running: bool,
+}
+
+/// Stage list ordering, `Recent` puts stages with the latest first start time first.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StageOrder {
+    #[default]
+    Name,
+    Recent,
+}
+
+impl StageOrder {
+    pub fn expression(self) -> &'static str {
+        match self {
+            StageOrder::Name => "stage_name",
+            StageOrder::Recent => "first_start desc, stage_name",
+        }
+    }
+}
+
+/// Filtering and paging of stage list. Filter is case insensitive substring of the stage name.
+#[derive(Debug, Clone, Default)]
+pub struct StageQuery {
+    pub filter: Option<String>,
+    pub order: StageOrder,
+    pub limit: Option<i64>,
+    pub offset: i64,
+}
+
+/// One page of stages and the number of stages matching filter.
+#[derive(Debug, Clone)]
+pub struct StagePage {
+    pub stages: Vec<Stage>,
+    pub total: i64,
+}
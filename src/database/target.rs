@@ -0,0 +1,22 @@
+use time::Timespec;
+
+/// Single row of stage data. Identifier and parent are set only when hierarchy is mapped.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub name: String,
+    pub start_time: Timespec,
+    pub end_time: Timespec,
+    pub group: String,
+    pub thread: String,
+    pub running: bool,
+    pub id: Option<String>,
+    pub parent: Option<String>,
+}
+
+/// Identifier, parent and thread of a target, enough to place its children.
+#[derive(Debug, Clone)]
+pub struct Ancestor {
+    pub id: String,
+    pub parent: Option<String>,
+    pub thread: String,
+}
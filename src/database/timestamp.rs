@@ -0,0 +1,429 @@
+use super::DatabaseError;
+use super::DatabaseResult;
+use postgres::rows::Row;
+use time::strptime;
+use time::Timespec;
+
+const FORMAT_ISO_8601: &str = "iso8601";
+const FORMAT_EPOCH_SECONDS: &str = "epoch_seconds";
+const FORMAT_EPOCH_MILLIS: &str = "epoch_millis";
+
+const ISO_8601_PATTERNS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S.%f%z",
+    "%Y-%m-%dT%H:%M:%S,%f%z",
+    "%Y-%m-%dT%H:%M:%S%z",
+    "%Y-%m-%dT%H:%M:%S.%f",
+    "%Y-%m-%dT%H:%M:%S,%f",
+    "%Y-%m-%dT%H:%M:%S",
+];
+
+const NANOSECONDS_IN_SECOND: f64 = 1_000_000_000.0;
+const MILLISECONDS_IN_SECOND: i64 = 1_000;
+const NANOSECONDS_IN_MILLISECOND: i64 = 1_000_000;
+// Range of JavaScript `Date`, times in it still fit in microseconds used by trace format.
+const MAX_EPOCH_MILLIS: i64 = 8_640_000_000_000_000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EpochUnit {
+    Seconds,
+    Milliseconds,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeSettings {
+    formats: Vec<String>,
+    epoch_unit: EpochUnit,
+    time_zone: String,
+}
+
+impl Default for TimeSettings {
+    fn default() -> TimeSettings {
+        TimeSettings {
+            formats: vec![
+                "%Y-%m-%d %H:%M:%S,%f".into(),
+                "%Y-%m-%d %H:%M:%S.%f".into(),
+                FORMAT_ISO_8601.into(),
+            ],
+            epoch_unit: EpochUnit::Seconds,
+            time_zone: "UTC".into(),
+        }
+    }
+}
+
+/// How time column value must be selected and decoded, depends on column type.
+#[derive(Debug, Clone, Copy)]
+pub enum TimeColumn {
+    TimestampTz,
+    Timestamp,
+    Integer,
+    Float,
+    Text,
+}
+
+impl TimeColumn {
+    pub fn from_data_type(data_type: &str) -> TimeColumn {
+        match data_type {
+            "timestamp with time zone" => TimeColumn::TimestampTz,
+            "timestamp without time zone" | "date" => TimeColumn::Timestamp,
+            "smallint" | "integer" | "bigint" => TimeColumn::Integer,
+            "real" | "double precision" | "numeric" => TimeColumn::Float,
+            _ => TimeColumn::Text,
+        }
+    }
+
+    /// Text values can not be compared in SQL, they are decoded with configured formats first.
+    pub fn is_text(self) -> bool {
+        matches!(self, TimeColumn::Text)
+    }
+
+    pub fn select_expression(self, column: &str) -> String {
+        match self {
+            TimeColumn::TimestampTz => column.into(),
+            TimeColumn::Timestamp => format!("{}::timestamp", column),
+            TimeColumn::Integer => format!("{}::bigint", column),
+            TimeColumn::Float => format!("{}::double precision", column),
+            TimeColumn::Text => format!("{}::text", column),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum TextFormat {
+    EpochSeconds,
+    EpochMillis,
+    Iso8601,
+    Pattern(String),
+}
+
+#[derive(Debug)]
+pub struct TimeDecoder {
+    formats: Vec<TextFormat>,
+    epoch_unit: EpochUnit,
+    offset: i64,
+}
+
+impl TimeDecoder {
+    pub fn new(settings: &TimeSettings) -> DatabaseResult<TimeDecoder> {
+        let formats = settings
+            .formats
+            .iter()
+            .map(|format| match format.as_str() {
+                FORMAT_ISO_8601 => TextFormat::Iso8601,
+                FORMAT_EPOCH_SECONDS => TextFormat::EpochSeconds,
+                FORMAT_EPOCH_MILLIS => TextFormat::EpochMillis,
+                pattern => TextFormat::Pattern(pattern.into()),
+            })
+            .collect();
+        let offset = parse_time_zone(&settings.time_zone)
+            .ok_or_else(|| DatabaseError::invalid_time_zone(&settings.time_zone))?;
+
+        Ok(TimeDecoder {
+            formats,
+            epoch_unit: settings.epoch_unit,
+            offset,
+        })
+    }
+
+    pub fn decode(
+        &self,
+        row: &Row,
+        index: usize,
+        column: TimeColumn,
+        column_name: &str,
+    ) -> DatabaseResult<Timespec> {
+        self.decode_optional(row, index, column, column_name)?
+            .ok_or_else(|| DatabaseError::null_value(column_name))
+    }
+
+    pub fn decode_optional(
+        &self,
+        row: &Row,
+        index: usize,
+        column: TimeColumn,
+        column_name: &str,
+    ) -> DatabaseResult<Option<Timespec>> {
+        let result = match column {
+            TimeColumn::TimestampTz => get_value(row, index, column_name)?,
+            TimeColumn::Timestamp => {
+                let value: Option<Timespec> = get_value(row, index, column_name)?;
+
+                value.map(|value| Timespec::new(value.sec - self.offset, value.nsec))
+            }
+            TimeColumn::Integer => {
+                let value: Option<i64> = get_value(row, index, column_name)?;
+
+                match value {
+                    Some(value) => Some(checked_time(
+                        match self.epoch_unit {
+                            EpochUnit::Seconds => value
+                                .checked_mul(MILLISECONDS_IN_SECOND)
+                                .and_then(from_epoch_millis),
+                            EpochUnit::Milliseconds => from_epoch_millis(value),
+                        },
+                        column_name,
+                        value,
+                    )?),
+                    None => None,
+                }
+            }
+            TimeColumn::Float => {
+                let value: Option<f64> = get_value(row, index, column_name)?;
+
+                match value {
+                    Some(value) => Some(checked_time(
+                        match self.epoch_unit {
+                            EpochUnit::Seconds => from_epoch_seconds(value),
+                            EpochUnit::Milliseconds => from_epoch_seconds(value / 1_000.0),
+                        },
+                        column_name,
+                        value,
+                    )?),
+                    None => None,
+                }
+            }
+            TimeColumn::Text => {
+                let value: Option<String> = get_value(row, index, column_name)?;
+
+                match value {
+                    Some(value) => Some(self.parse(&value, column_name)?),
+                    None => None,
+                }
+            }
+        };
+
+        Ok(result)
+    }
+
+    pub fn parse(&self, value: &str, column_name: &str) -> DatabaseResult<Timespec> {
+        let value = value.trim();
+
+        for format in &self.formats {
+            let result = match format {
+                TextFormat::EpochSeconds => value.parse().ok().and_then(from_epoch_seconds),
+                TextFormat::EpochMillis => value.parse().ok().and_then(from_epoch_millis),
+                TextFormat::Iso8601 => ISO_8601_PATTERNS
+                    .iter()
+                    .filter_map(|pattern| self.parse_pattern(value, pattern))
+                    .next(),
+                TextFormat::Pattern(pattern) => self.parse_pattern(value, pattern),
+            };
+
+            if let Some(result) = result {
+                return Ok(result);
+            }
+        }
+
+        Err(DatabaseError::unknown_time_format(column_name, value))
+    }
+
+    fn parse_pattern(&self, value: &str, pattern: &str) -> Option<Timespec> {
+        let mut time = strptime(value, pattern).ok()?;
+        let offset = if pattern.contains("%z") {
+            i64::from(time.tm_utcoff)
+        } else {
+            self.offset
+        };
+
+        // `Tm::to_timespec` treats any non zero offset as local time zone, so offset is applied
+        // manually.
+        time.tm_utcoff = 0;
+
+        let result = time.to_timespec();
+
+        Some(Timespec::new(result.sec - offset, result.nsec))
+    }
+}
+
+fn get_value<T>(row: &Row, index: usize, column_name: &str) -> DatabaseResult<T>
+where
+    T: postgres::types::FromSql,
+{
+    row.get_opt(index)
+        .ok_or_else(DatabaseError::column_not_exists)?
+        .map_err(|error| DatabaseError::conversion_error(error, column_name))
+}
+
+/// Numeric value out of supported range is reported the same way as unparsable text.
+fn checked_time<T: ToString>(
+    time: Option<Timespec>,
+    column_name: &str,
+    value: T,
+) -> DatabaseResult<Timespec> {
+    time.ok_or_else(|| DatabaseError::unknown_time_format(column_name, &value.to_string()))
+}
+
+/// Returns `None` for infinity, NaN and values out of supported range.
+fn from_epoch_seconds(value: f64) -> Option<Timespec> {
+    let max_seconds = (MAX_EPOCH_MILLIS / MILLISECONDS_IN_SECOND) as f64;
+
+    if !(-max_seconds..=max_seconds).contains(&value) {
+        return None;
+    }
+
+    let seconds = value.floor();
+    let nanoseconds = ((value - seconds) * NANOSECONDS_IN_SECOND) as i32;
+
+    Some(Timespec::new(seconds as i64, nanoseconds))
+}
+
+/// Returns `None` for values out of supported range.
+fn from_epoch_millis(value: i64) -> Option<Timespec> {
+    if !(-MAX_EPOCH_MILLIS..=MAX_EPOCH_MILLIS).contains(&value) {
+        return None;
+    }
+
+    let seconds = value.div_euclid(MILLISECONDS_IN_SECOND);
+    let milliseconds = value.rem_euclid(MILLISECONDS_IN_SECOND);
+
+    Some(Timespec::new(
+        seconds,
+        (milliseconds * NANOSECONDS_IN_MILLISECOND) as i32,
+    ))
+}
+
+/// Parses time zone as fixed offset from UTC in seconds. Accepts `UTC`, `Z` and `+HH:MM` or
+/// `-HH:MM` forms. Named zones are rejected, their offset depends on the date.
+fn parse_time_zone(zone: &str) -> Option<i64> {
+    let zone = zone.trim();
+
+    if zone.eq_ignore_ascii_case("utc") || zone == "Z" {
+        return Some(0);
+    }
+
+    let sign = match zone.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let mut parts = zone[1..].splitn(2, ':');
+    let hours: i64 = parts.next()?.parse().ok()?;
+    let minutes: i64 = parts.next().unwrap_or("0").parse().ok()?;
+
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    Some(sign * (hours * 3_600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoder(formats: &[&str], time_zone: &str) -> TimeDecoder {
+        let settings = TimeSettings {
+            formats: formats.iter().map(|format| format.to_string()).collect(),
+            time_zone: time_zone.into(),
+            ..TimeSettings::default()
+        };
+
+        TimeDecoder::new(&settings).unwrap()
+    }
+
+    #[test]
+    fn time_zones() {
+        assert_eq!(parse_time_zone("UTC"), Some(0));
+        assert_eq!(parse_time_zone("utc"), Some(0));
+        assert_eq!(parse_time_zone("Z"), Some(0));
+        assert_eq!(parse_time_zone("+03:00"), Some(10_800));
+        assert_eq!(parse_time_zone("-05:30"), Some(-19_800));
+        assert_eq!(parse_time_zone("+03"), Some(10_800));
+        assert_eq!(parse_time_zone("+24:00"), None);
+        assert_eq!(parse_time_zone("Europe/Berlin"), None);
+        assert_eq!(parse_time_zone(""), None);
+    }
+
+    #[test]
+    fn default_formats_with_offset() {
+        let decoder = decoder(&["%Y-%m-%d %H:%M:%S,%f", "iso8601"], "+01:00");
+
+        assert_eq!(
+            decoder.parse("2020-01-01 10:00:15,250", "start").unwrap(),
+            Timespec::new(1_577_869_215, 250_000_000)
+        );
+        assert_eq!(
+            decoder.parse(" 2020-01-01T10:00:15 ", "start").unwrap(),
+            Timespec::new(1_577_869_215, 0)
+        );
+        assert_eq!(
+            decoder.parse("2020-01-01T10:00:15+0000", "start").unwrap(),
+            Timespec::new(1_577_872_815, 0)
+        );
+    }
+
+    #[test]
+    fn epoch_formats() {
+        let decoder = decoder(&["epoch_millis", "epoch_seconds"], "UTC");
+
+        assert_eq!(
+            decoder.parse("1577872815250", "start").unwrap(),
+            Timespec::new(1_577_872_815, 250_000_000)
+        );
+        assert_eq!(
+            decoder.parse("1577872815.5", "start").unwrap(),
+            Timespec::new(1_577_872_815, 500_000_000)
+        );
+        assert_eq!(from_epoch_millis(-1), Some(Timespec::new(-1, 999_000_000)));
+    }
+
+    #[test]
+    fn non_finite_and_out_of_range_epochs_are_rejected() {
+        let decoder = decoder(&["epoch_seconds", "epoch_millis"], "UTC");
+
+        for value in &[
+            "inf",
+            "-inf",
+            "NaN",
+            "infinity",
+            "1e300",
+            "-9000000000000000000",
+        ] {
+            assert!(
+                matches!(
+                    decoder.parse(value, "start"),
+                    Err(DatabaseError::TimeParseError { .. })
+                ),
+                "{}",
+                value
+            );
+        }
+
+        assert_eq!(from_epoch_seconds(f64::NAN), None);
+        assert_eq!(
+            from_epoch_seconds(8.64e12),
+            Some(Timespec::new(8_640_000_000_000, 0))
+        );
+        assert_eq!(from_epoch_seconds(8.64e12 + 1.0), None);
+        assert_eq!(from_epoch_millis(i64::MIN), None);
+        assert_eq!(
+            from_epoch_millis(-MAX_EPOCH_MILLIS),
+            Some(Timespec::new(-8_640_000_000_000, 0))
+        );
+        assert!(checked_time(None, "start", f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn unknown_format() {
+        let decoder = decoder(&["iso8601"], "UTC");
+
+        assert!(matches!(
+            decoder.parse("garbage", "start"),
+            Err(DatabaseError::TimeParseError { .. })
+        ));
+    }
+
+    #[test]
+    fn named_time_zone_is_rejected() {
+        let settings = TimeSettings {
+            time_zone: "Europe/Berlin".into(),
+            ..TimeSettings::default()
+        };
+
+        assert!(matches!(
+            TimeDecoder::new(&settings),
+            Err(DatabaseError::InvalidTimeZone { .. })
+        ));
+    }
+}
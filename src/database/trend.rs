@@ -0,0 +1,10 @@
+use super::Target;
+use time::Timespec;
+
+/// Target of one of the stages selected by trend query with start time of its stage.
+#[derive(Debug, Clone)]
+pub struct TrendTarget {
+    pub stage: String,
+    pub stage_start: Timespec,
+    pub target: Target,
+}
@@ -0,0 +1,118 @@
+use super::Row;
+use super::RowWriter;
+use crate::database::QualityReport;
+use std::io::Result as IoResult;
+use std::io::Write;
+
+const HEADER: &str = "name,start,end,group,thread,running,critical,kind\r\n";
+const ERRORS_HEADER: &str = "position,column,value,message\r\n";
+
+/// RFC 4180 CSV with header line. Skipped rows follow after an empty line as second table with
+/// its own header.
+#[derive(Debug)]
+pub struct CsvWriter {}
+
+impl CsvWriter {
+    pub fn new() -> CsvWriter {
+        CsvWriter {}
+    }
+}
+
+impl RowWriter for CsvWriter {
+    fn write_header(&mut self, writer: &mut dyn Write) -> IoResult<()> {
+        writer.write_all(HEADER.as_bytes())
+    }
+
+    fn write_row(&mut self, writer: &mut dyn Write, row: &Row) -> IoResult<()> {
+        writer.write_fmt(format_args!(
+            "{},{},{},{},{},{},{},{}\r\n",
+            quote(&row.name),
+            row.start,
+            row.end,
+            quote(&row.group),
+            quote(&row.thread),
+            row.running,
+            row.critical,
+            row.kind.name()
+        ))
+    }
+
+    fn write_quality(&mut self, writer: &mut dyn Write, report: &QualityReport) -> IoResult<()> {
+        writer.write_all(b"\r\n")?;
+        writer.write_all(ERRORS_HEADER.as_bytes())?;
+
+        for error in &report.errors {
+            writer.write_fmt(format_args!(
+                "{},{},{},{}\r\n",
+                error.position,
+                quote(error.column.as_deref().unwrap_or("")),
+                quote(error.value.as_deref().unwrap_or("")),
+                quote(&error.message)
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn quote(value: &str) -> String {
+    if value.contains(&[',', '"', '\r', '\n'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseError;
+    use crate::database::IntervalPolicy;
+
+    #[test]
+    fn values_are_quoted_only_when_needed() {
+        assert_eq!(quote("compile"), "compile");
+        assert_eq!(quote("link;x"), "link;x");
+        assert_eq!(quote("a,b"), "\"a,b\"");
+        assert_eq!(quote("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(quote("two\nlines"), "\"two\nlines\"");
+        assert_eq!(quote(""), "");
+    }
+
+    #[test]
+    fn rows_end_with_crlf() {
+        let row = Row::task("a,b", 1, 2).group("g").thread("t");
+        let mut writer = CsvWriter::new();
+        let mut output = Vec::new();
+
+        writer.write_header(&mut output).unwrap();
+        writer.write_row(&mut output, &row).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("{}\"a,b\",1,2,g,t,false,false,task\r\n", HEADER)
+        );
+    }
+
+    #[test]
+    fn skipped_rows_are_second_table() {
+        let mut report = QualityReport::new(IntervalPolicy::Clamp);
+        let mut output = Vec::new();
+
+        report
+            .add_error(2, DatabaseError::null_value("start"), 10)
+            .unwrap();
+        CsvWriter::new()
+            .write_quality(&mut output, &report)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "\r\n{}2,start,,{}\r\n",
+                ERRORS_HEADER,
+                quote(&report.errors[0].message)
+            )
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use super::Row;
+use super::RowWriter;
+use crate::database::QualityReport;
+use std::io::Result as IoResult;
+use std::io::Write;
+
+/// Semicolon delimited format read by the web interface: name, start, end, group, thread,
+/// running and critical flags, row kind and nesting depth. Skipped rows follow after an empty
+/// line: position, column, value and message. Delimiters and control characters are removed from
+/// text fields because format has no quoting.
+#[derive(Debug)]
+pub struct DlmWriter {}
+
+impl DlmWriter {
+    pub fn new() -> DlmWriter {
+        DlmWriter {}
+    }
+}
+
+impl RowWriter for DlmWriter {
+    fn write_row(&mut self, writer: &mut dyn Write, row: &Row) -> IoResult<()> {
+        writer.write_fmt(format_args!(
+            "{};{};{};{};{};{};{};{};{}\n",
+            sanitize(&row.name),
+            row.start,
+            row.end,
+            sanitize(&row.group),
+            sanitize(&row.thread),
+            if row.running { 1 } else { 0 },
+            if row.critical { 1 } else { 0 },
+            row.kind.name(),
+            row.depth
+        ))
+    }
+
+    fn write_quality(&mut self, writer: &mut dyn Write, report: &QualityReport) -> IoResult<()> {
+        writer.write_all(b"\n")?;
+
+        for error in &report.errors {
+            writer.write_fmt(format_args!(
+                "{};{};{};{}\n",
+                error.position,
+                sanitize(error.column.as_deref().unwrap_or("")),
+                sanitize(error.value.as_deref().unwrap_or("")),
+                sanitize(&error.message)
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|&ch| !ch.is_control() && ch != ';')
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseError;
+    use crate::database::IntervalPolicy;
+
+    #[test]
+    fn rows_have_kind_and_depth() {
+        let row = Row::task("link;x\n", 1, 2)
+            .group("g")
+            .thread("t")
+            .parent("1")
+            .depth(2);
+        let mut output = Vec::new();
+
+        DlmWriter::new().write_row(&mut output, &row).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "linkx;1;2;g;t;0;0;task;2\n"
+        );
+    }
+
+    #[test]
+    fn skipped_rows_follow_empty_line() {
+        let mut report = QualityReport::new(IntervalPolicy::Clamp);
+        let error = DatabaseError::unknown_time_format("start;time", "yesterday");
+        let message = error.to_string().replace(';', "");
+        let mut output = Vec::new();
+        let mut writer = DlmWriter::new();
+
+        report.add_error(3, error, 10).unwrap();
+        writer
+            .write_row(&mut output, &Row::task("a", 1, 2))
+            .unwrap();
+        writer.write_quality(&mut output, &report).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("a;1;2;;;0;0;task;0\n\n3;starttime;yesterday;{}\n", message)
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use super::QualityMetadata;
+use super::Row;
+use super::RowWriter;
+use crate::database::QualityReport;
+use serde::Serialize;
+use std::io::Error as IoError;
+use std::io::Result as IoResult;
+use std::io::Write;
+
+/// Single JSON array of row objects, quality report is the last element `{"quality": ...}`.
+#[derive(Debug)]
+pub struct JsonWriter {
+    is_first: bool,
+}
+
+impl JsonWriter {
+    pub fn new() -> JsonWriter {
+        JsonWriter { is_first: true }
+    }
+}
+
+impl RowWriter for JsonWriter {
+    fn write_header(&mut self, writer: &mut dyn Write) -> IoResult<()> {
+        writer.write_all(b"[")
+    }
+
+    fn write_row(&mut self, writer: &mut dyn Write, row: &Row) -> IoResult<()> {
+        if self.is_first {
+            self.is_first = false;
+        } else {
+            writer.write_all(b",")?;
+        }
+
+        write_json(writer, row)
+    }
+
+    fn write_quality(&mut self, writer: &mut dyn Write, report: &QualityReport) -> IoResult<()> {
+        if !self.is_first {
+            writer.write_all(b",")?;
+        }
+
+        write_json(writer, &QualityMetadata { quality: report })
+    }
+
+    fn write_footer(&mut self, writer: &mut dyn Write) -> IoResult<()> {
+        writer.write_all(b"]")
+    }
+}
+
+/// Newline delimited JSON, one row object per line, quality report is the last line
+/// `{"quality": ...}`.
+#[derive(Debug)]
+pub struct NdjsonWriter {}
+
+impl NdjsonWriter {
+    pub fn new() -> NdjsonWriter {
+        NdjsonWriter {}
+    }
+}
+
+impl RowWriter for NdjsonWriter {
+    fn write_row(&mut self, writer: &mut dyn Write, row: &Row) -> IoResult<()> {
+        write_json(writer, row)?;
+        writer.write_all(b"\n")
+    }
+
+    fn write_quality(&mut self, writer: &mut dyn Write, report: &QualityReport) -> IoResult<()> {
+        write_json(writer, &QualityMetadata { quality: report })?;
+        writer.write_all(b"\n")
+    }
+}
+
+fn write_json<T: Serialize>(writer: &mut dyn Write, value: &T) -> IoResult<()> {
+    serde_json::to_writer(writer, value).map_err(IoError::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseError;
+    use crate::database::IntervalPolicy;
+    use serde_json::Value;
+
+    fn report() -> QualityReport {
+        let mut report = QualityReport::new(IntervalPolicy::Clamp);
+
+        report
+            .add_error(2, DatabaseError::unknown_time_format("start", "never"), 10)
+            .unwrap();
+
+        report
+    }
+
+    #[test]
+    fn quality_is_last_array_element() {
+        let mut writer = JsonWriter::new();
+        let mut output = Vec::new();
+
+        writer.write_header(&mut output).unwrap();
+        writer
+            .write_row(&mut output, &Row::task("a", 1, 2))
+            .unwrap();
+        writer.write_quality(&mut output, &report()).unwrap();
+        writer.write_footer(&mut output).unwrap();
+
+        let value: Value = serde_json::from_slice(&output).unwrap();
+
+        assert_eq!(value[0]["name"], "a");
+        assert_eq!(value[1]["quality"]["unparsable"], 1);
+        assert_eq!(value[1]["quality"]["errors"][0]["position"], 2);
+        assert_eq!(value[1]["quality"]["errors"][0]["value"], "never");
+    }
+
+    #[test]
+    fn quality_is_last_line() {
+        let mut writer = NdjsonWriter::new();
+        let mut output = Vec::new();
+
+        writer
+            .write_row(&mut output, &Row::task("a", 1, 2))
+            .unwrap();
+        writer.write_quality(&mut output, &report()).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["quality"]["errors"][0]["column"], "start");
+    }
+}
@@ -0,0 +1,579 @@
+mod csv;
+mod dlm;
+mod json;
+mod trace;
+
+use self::csv::CsvWriter;
+use self::dlm::DlmWriter;
+use self::json::JsonWriter;
+use self::json::NdjsonWriter;
+use self::trace::TraceWriter;
+use crate::database::Ancestor;
+use crate::database::DatabaseResult;
+use crate::database::Event;
+use crate::database::PostgreSQL;
+use crate::database::QualityReport;
+use crate::database::Target;
+use iron::mime::Mime;
+use iron::mime::SubLevel;
+use iron::mime::TopLevel;
+use std::cmp::Ordering;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::io::Result as IoResult;
+use std::io::Write;
+use std::str::FromStr;
+use time::Timespec;
+
+pub use self::trace::TraceProcess;
+
+const SECOND_MULTIPLIER: i64 = 1_000;
+const NANOSECOND_DIVIDER: i64 = 1_000_000;
+const NANOSECONDS_IN_MICROSECOND: i64 = 1_000;
+const MICROSECONDS_IN_MILLISECOND: i64 = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Dlm,
+    Csv,
+    Json,
+    Ndjson,
+    Trace,
+}
+
+/// Settings used by formats which need more than rows.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    pub stage: String,
+    pub trace_process: TraceProcess,
+}
+
+impl Format {
+    /// Selects format by media type from `Accept` header.
+    pub fn from_mime(mime: &Mime) -> Option<Format> {
+        match mime {
+            Mime(TopLevel::Text, SubLevel::Plain, _) => Some(Format::Dlm),
+            Mime(TopLevel::Text, SubLevel::Ext(ext), _) if ext == "csv" => Some(Format::Csv),
+            Mime(TopLevel::Application, SubLevel::Json, _) => Some(Format::Json),
+            Mime(TopLevel::Application, SubLevel::Ext(ext), _)
+                if ext == "x-ndjson" || ext == "ndjson" =>
+            {
+                Some(Format::Ndjson)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> Mime {
+        match self {
+            Format::Dlm => Mime(TopLevel::Text, SubLevel::Plain, vec![]),
+            Format::Csv => Mime(TopLevel::Text, SubLevel::Ext("csv".into()), vec![]),
+            Format::Json | Format::Trace => Mime(TopLevel::Application, SubLevel::Json, vec![]),
+            Format::Ndjson => Mime(
+                TopLevel::Application,
+                SubLevel::Ext("x-ndjson".into()),
+                vec![],
+            ),
+        }
+    }
+
+    pub fn writer(self, options: &ExportOptions) -> Box<dyn RowWriter> {
+        match self {
+            Format::Dlm => Box::new(DlmWriter::new()),
+            Format::Csv => Box::new(CsvWriter::new()),
+            Format::Json => Box::new(JsonWriter::new()),
+            Format::Ndjson => Box::new(NdjsonWriter::new()),
+            Format::Trace => Box::new(TraceWriter::new(&options.stage, options.trace_process)),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Format, String> {
+        match value {
+            "dlm" => Ok(Format::Dlm),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "trace" => Ok(Format::Trace),
+            _ => Err(format!(
+                "Unknown format `{}`, expected dlm, csv, json, ndjson or trace",
+                value
+            )),
+        }
+    }
+}
+
+/// Writes rows one by one, so output never has to be kept in memory.
+pub trait RowWriter {
+    fn write_header(&mut self, _writer: &mut dyn Write) -> IoResult<()> {
+        Ok(())
+    }
+
+    fn write_row(&mut self, writer: &mut dyn Write, row: &Row) -> IoResult<()>;
+
+    /// Writes data quality report after all rows, called only when some rows were skipped
+    /// because they can not be decoded.
+    fn write_quality(&mut self, writer: &mut dyn Write, report: &QualityReport) -> IoResult<()>;
+
+    fn write_footer(&mut self, _writer: &mut dyn Write) -> IoResult<()> {
+        Ok(())
+    }
+}
+
+/// Trailing object of JSON formats, keeps data quality report apart from rows.
+#[derive(Debug, Serialize)]
+struct QualityMetadata<'a> {
+    quality: &'a QualityReport,
+}
+
+/// Tasks are intervals. Milestones are points in time on one thread, they have equal start and end
+/// time. Markers are points in time which apply to all threads, their thread is empty.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RowKind {
+    #[default]
+    Task,
+    Milestone,
+    Marker,
+}
+
+impl RowKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            RowKind::Task => "task",
+            RowKind::Milestone => "milestone",
+            RowKind::Marker => "marker",
+        }
+    }
+}
+
+/// Target representation shared by all output formats, times are milliseconds since epoch,
+/// microseconds within the millisecond are kept for trace output only. `critical` is set only
+/// when critical path was requested. Nested targets have `id` and `parent` when hierarchy is
+/// mapped, `depth` is set by `Nesting`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Row {
+    pub kind: RowKind,
+    pub name: String,
+    pub start: i64,
+    pub end: i64,
+    pub group: String,
+    pub thread: String,
+    pub running: bool,
+    pub critical: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    pub depth: usize,
+    #[serde(skip)]
+    pub start_micros: i64,
+    #[serde(skip)]
+    pub end_micros: i64,
+}
+
+impl Row {
+    pub fn from_target(target: &Target) -> Row {
+        // Kind is decided by full precision times, so targets shorter than a millisecond stay
+        // tasks.
+        let (kind, end_time) = match target.start_time.cmp(&target.end_time) {
+            Ordering::Less => (RowKind::Task, target.end_time),
+            Ordering::Equal => (RowKind::Milestone, target.end_time),
+            // Stage data is fixed by interval policy already, a target from other source which
+            // still ends before it starts is kept as a milestone at its start like
+            // `IntervalPolicy::Milestone` does.
+            Ordering::Greater => (RowKind::Milestone, target.start_time),
+        };
+
+        Row {
+            kind,
+            name: target.name.clone(),
+            start: to_millis(target.start_time),
+            end: to_millis(end_time),
+            group: target.group.clone(),
+            thread: target.thread.clone(),
+            running: target.running,
+            critical: false,
+            id: target.id.clone(),
+            parent: target.parent.clone(),
+            depth: 0,
+            start_micros: sub_millis_micros(target.start_time),
+            end_micros: sub_millis_micros(end_time),
+        }
+    }
+
+    pub fn from_event(event: &Event) -> Row {
+        let time = to_millis(event.time);
+        let micros = sub_millis_micros(event.time);
+
+        Row {
+            kind: if event.thread.is_some() {
+                RowKind::Milestone
+            } else {
+                RowKind::Marker
+            },
+            name: event.name.clone(),
+            start: time,
+            end: time,
+            group: event.group.clone(),
+            thread: event.thread.clone().unwrap_or_default(),
+            running: false,
+            critical: false,
+            id: None,
+            parent: None,
+            depth: 0,
+            start_micros: micros,
+            end_micros: micros,
+        }
+    }
+
+    pub fn is_task(&self) -> bool {
+        self.kind == RowKind::Task
+    }
+}
+
+#[cfg(test)]
+impl Row {
+    /// Top-level task for tests, other fields are set by chained methods.
+    pub fn task(name: &str, start: i64, end: i64) -> Row {
+        Row {
+            name: name.into(),
+            start,
+            end,
+            ..Row::default()
+        }
+    }
+
+    pub fn milestone(name: &str, time: i64) -> Row {
+        Row {
+            kind: RowKind::Milestone,
+            ..Row::task(name, time, time)
+        }
+    }
+
+    pub fn group(self, group: &str) -> Row {
+        Row {
+            group: group.into(),
+            ..self
+        }
+    }
+
+    pub fn thread(self, thread: &str) -> Row {
+        Row {
+            thread: thread.into(),
+            ..self
+        }
+    }
+
+    pub fn running(self) -> Row {
+        Row {
+            running: true,
+            ..self
+        }
+    }
+
+    pub fn id(self, id: &str) -> Row {
+        Row {
+            id: Some(id.into()),
+            ..self
+        }
+    }
+
+    pub fn parent(self, parent: &str) -> Row {
+        Row {
+            parent: Some(parent.into()),
+            ..self
+        }
+    }
+
+    pub fn depth(self, depth: usize) -> Row {
+        Row { depth, ..self }
+    }
+}
+
+/// Places nested rows on the lane of their top-level ancestor one level deeper than the parent.
+/// Rows must come in order of start time, so parents start before their children or at the same
+/// time. A row whose parent is not seen yet waits until its parent or a row with later start
+/// comes, then it stays on its own lane as top-level row.
+#[derive(Debug, Default)]
+pub struct Nesting {
+    parents: HashMap<String, (usize, String)>,
+    waiting: HashMap<String, Vec<Row>>,
+    waiting_start: Option<i64>,
+}
+
+impl Nesting {
+    pub fn new() -> Nesting {
+        Nesting::default()
+    }
+
+    /// Registers target read before, e.g. ancestor of rows sent by previous update.
+    pub fn add_parent(&mut self, ancestor: &Ancestor) {
+        let (depth, thread) = match ancestor.parent.as_ref().and_then(|id| self.parents.get(id)) {
+            Some((depth, thread)) => (depth + 1, thread.clone()),
+            None => (0, ancestor.thread.clone()),
+        };
+
+        self.parents.insert(ancestor.id.clone(), (depth, thread));
+    }
+
+    /// Passes the row to `callback` unless it waits for its parent.
+    pub fn push<F, E>(&mut self, row: Row, callback: &mut F) -> Result<(), E>
+    where
+        F: FnMut(Row) -> Result<(), E>,
+    {
+        if self.waiting_start.is_some_and(|start| row.start > start) {
+            self.finish(callback)?;
+        }
+
+        match &row.parent {
+            Some(id) if !self.parents.contains_key(id) => {
+                self.waiting_start = Some(row.start);
+                self.waiting.entry(id.clone()).or_default().push(row);
+
+                Ok(())
+            }
+            _ => self.emit(row, callback),
+        }
+    }
+
+    /// Passes waiting rows as top-level rows, their parents are not in the data.
+    pub fn finish<F, E>(&mut self, callback: &mut F) -> Result<(), E>
+    where
+        F: FnMut(Row) -> Result<(), E>,
+    {
+        while !self.waiting.is_empty() {
+            // Rows waiting for other waiting rows are passed with them, a cycle is broken at one
+            // row.
+            let ids: HashSet<&String> = self
+                .waiting
+                .values()
+                .flatten()
+                .filter_map(|row| row.id.as_ref())
+                .collect();
+            let mut parents: Vec<String> = self
+                .waiting
+                .keys()
+                .filter(|id| !ids.contains(id))
+                .cloned()
+                .collect();
+
+            if parents.is_empty() {
+                parents = self.waiting.keys().min().cloned().into_iter().collect();
+            }
+
+            let mut rows: Vec<Row> = parents
+                .iter()
+                .filter_map(|id| self.waiting.remove(id))
+                .flatten()
+                .collect();
+
+            rows.sort_by_key(|row| Reverse(row.end));
+
+            for row in rows {
+                self.emit(row, callback)?;
+            }
+        }
+
+        self.waiting_start = None;
+
+        Ok(())
+    }
+
+    /// Nests the row and passes it to `callback` followed by its waiting descendants.
+    fn emit<F, E>(&mut self, row: Row, callback: &mut F) -> Result<(), E>
+    where
+        F: FnMut(Row) -> Result<(), E>,
+    {
+        let mut rows = vec![row];
+
+        while let Some(mut row) = rows.pop() {
+            if let Some((depth, thread)) = row.parent.as_ref().and_then(|id| self.parents.get(id)) {
+                row.depth = depth + 1;
+                row.thread = thread.clone();
+            }
+
+            if let Some(id) = &row.id {
+                self.parents
+                    .insert(id.clone(), (row.depth, row.thread.clone()));
+
+                if let Some(children) = self.waiting.remove(id) {
+                    rows.extend(children.into_iter().rev());
+                }
+            }
+
+            callback(row)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads all targets of the stage into memory, used when output can not be streamed.
+pub fn read_rows(postgres: &PostgreSQL, database: &str, stage: &str) -> DatabaseResult<Vec<Row>> {
+    let mut rows = Vec::new();
+    let mut nesting = Nesting::new();
+
+    let mut push = |row| {
+        rows.push(row);
+
+        Ok::<(), Infallible>(())
+    };
+
+    postgres
+        .data(database, stage, |target| {
+            nesting.push(Row::from_target(target), &mut push)
+        })?
+        .unwrap_or_else(|never| match never {});
+    nesting
+        .finish(&mut push)
+        .unwrap_or_else(|never| match never {});
+
+    Ok(rows)
+}
+
+/// Reads point events of the stage from mapped event table as milestone and marker rows.
+pub fn read_events(postgres: &PostgreSQL, database: &str, stage: &str) -> DatabaseResult<Vec<Row>> {
+    Ok(postgres
+        .events(database, stage)?
+        .iter()
+        .map(Row::from_event)
+        .collect())
+}
+
+pub fn to_millis(time: Timespec) -> i64 {
+    SECOND_MULTIPLIER * time.sec + time.nsec as i64 / NANOSECOND_DIVIDER
+}
+
+/// Microseconds after the last whole millisecond of `time`.
+fn sub_millis_micros(time: Timespec) -> i64 {
+    time.nsec as i64 / NANOSECONDS_IN_MICROSECOND % MICROSECONDS_IN_MILLISECOND
+}
+
+pub fn from_millis(millis: i64) -> Timespec {
+    Timespec::new(
+        millis.div_euclid(SECOND_MULTIPLIER),
+        (millis.rem_euclid(SECOND_MULTIPLIER) * NANOSECOND_DIVIDER) as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nest(nesting: &mut Nesting, rows: Vec<Row>) -> Vec<(String, usize, String)> {
+        let mut result = Vec::new();
+        let mut push = |row: Row| {
+            result.push((row.name, row.depth, row.thread));
+
+            Ok::<(), Infallible>(())
+        };
+
+        for row in rows {
+            nesting
+                .push(row, &mut push)
+                .unwrap_or_else(|never| match never {});
+        }
+
+        nesting
+            .finish(&mut push)
+            .unwrap_or_else(|never| match never {});
+
+        result
+    }
+
+    fn nested(name: &str, depth: usize, thread: &str) -> (String, usize, String) {
+        (name.into(), depth, thread.into())
+    }
+
+    #[test]
+    fn children_after_parent() {
+        let rows = vec![
+            Row::task("1", 0, 10).thread("a").id("1"),
+            Row::task("2", 5, 15).thread("b").id("2").parent("1"),
+            Row::task("3", 5, 15).thread("c").id("3").parent("2"),
+        ];
+
+        assert_eq!(
+            nest(&mut Nesting::new(), rows),
+            vec![
+                nested("1", 0, "a"),
+                nested("2", 1, "a"),
+                nested("3", 2, "a")
+            ]
+        );
+    }
+
+    #[test]
+    fn children_before_parent_with_same_start() {
+        let rows = vec![
+            Row::task("3", 0, 10).thread("c").id("3").parent("2"),
+            Row::task("2", 0, 10).thread("b").id("2").parent("1"),
+            Row::task("1", 0, 10).thread("a").id("1"),
+            Row::task("4", 5, 15).thread("d").id("4"),
+        ];
+
+        assert_eq!(
+            nest(&mut Nesting::new(), rows),
+            vec![
+                nested("1", 0, "a"),
+                nested("2", 1, "a"),
+                nested("3", 2, "a"),
+                nested("4", 0, "d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_parents_and_cycles_stay_top_level() {
+        let rows = vec![
+            Row::task("1", 0, 10).thread("a").id("1").parent("2"),
+            Row::task("2", 0, 10).thread("b").id("2").parent("1"),
+            Row::task("3", 0, 10).thread("c").id("3").parent("9"),
+            Row::task("4", 5, 15).thread("d").id("4").parent("3"),
+        ];
+        let mut result = nest(&mut Nesting::new(), rows);
+
+        result.sort();
+
+        assert_eq!(
+            result,
+            vec![
+                nested("1", 1, "b"),
+                nested("2", 0, "b"),
+                nested("3", 0, "c"),
+                nested("4", 1, "c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parents_of_previous_update() {
+        let mut nesting = Nesting::new();
+
+        nesting.add_parent(&Ancestor {
+            id: "1".into(),
+            parent: None,
+            thread: "a".into(),
+        });
+        nesting.add_parent(&Ancestor {
+            id: "2".into(),
+            parent: Some("1".into()),
+            thread: "b".into(),
+        });
+
+        assert_eq!(
+            nest(
+                &mut nesting,
+                vec![Row::task("3", 0, 10).thread("c").id("3").parent("2")]
+            ),
+            vec![nested("3", 2, "a")]
+        );
+    }
+}
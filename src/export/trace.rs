@@ -0,0 +1,318 @@
+use super::QualityMetadata;
+use super::Row;
+use super::RowKind;
+use super::RowWriter;
+use crate::database::QualityReport;
+use std::collections::HashMap;
+use std::io::Error as IoError;
+use std::io::Result as IoResult;
+use std::io::Write;
+use std::str::FromStr;
+
+const MICROSECONDS_IN_MILLISECOND: i64 = 1_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceProcess {
+    #[default]
+    Stage,
+    Group,
+}
+
+impl FromStr for TraceProcess {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<TraceProcess, String> {
+        match value {
+            "stage" => Ok(TraceProcess::Stage),
+            "group" => Ok(TraceProcess::Group),
+            _ => Err(format!(
+                "Unknown trace process `{}`, expected stage or group",
+                value
+            )),
+        }
+    }
+}
+
+/// Chrome Trace Event Format readable by `chrome://tracing` and Perfetto. Every task becomes
+/// complete event, milestones become thread scoped instant events and markers become global
+/// instant events without thread. Process and thread names are written as metadata events on
+/// first use. Quality report is written as `{"quality": ...}` in top level `metadata`.
+#[derive(Debug)]
+pub struct TraceWriter {
+    stage: String,
+    process: TraceProcess,
+    processes: HashMap<String, usize>,
+    threads: HashMap<(usize, String), usize>,
+    is_first: bool,
+    metadata: Option<String>,
+}
+
+impl TraceWriter {
+    pub fn new(stage: &str, process: TraceProcess) -> TraceWriter {
+        TraceWriter {
+            stage: stage.into(),
+            process,
+            processes: HashMap::new(),
+            threads: HashMap::new(),
+            is_first: true,
+            metadata: None,
+        }
+    }
+
+    fn write_event(&mut self, writer: &mut dyn Write, event: &Event) -> IoResult<()> {
+        if self.is_first {
+            self.is_first = false;
+        } else {
+            writer.write_all(b",")?;
+        }
+
+        serde_json::to_writer(writer, event).map_err(IoError::other)
+    }
+
+    fn process_id(&mut self, writer: &mut dyn Write, row: &Row) -> IoResult<usize> {
+        let name = match self.process {
+            TraceProcess::Stage => &self.stage,
+            TraceProcess::Group => &row.group,
+        };
+
+        if let Some(&pid) = self.processes.get(name) {
+            return Ok(pid);
+        }
+
+        let name = name.clone();
+        let pid = self.processes.len() + 1;
+
+        self.processes.insert(name.clone(), pid);
+        self.write_event(writer, &Event::metadata("process_name", pid, 0, name))?;
+
+        Ok(pid)
+    }
+
+    fn thread_id(&mut self, writer: &mut dyn Write, pid: usize, row: &Row) -> IoResult<usize> {
+        let key = (pid, row.thread.clone());
+
+        if let Some(&tid) = self.threads.get(&key) {
+            return Ok(tid);
+        }
+
+        let tid = self.threads.len() + 1;
+
+        self.threads.insert(key, tid);
+        self.write_event(
+            writer,
+            &Event::metadata("thread_name", pid, tid, row.thread.clone()),
+        )?;
+
+        Ok(tid)
+    }
+}
+
+impl RowWriter for TraceWriter {
+    fn write_header(&mut self, writer: &mut dyn Write) -> IoResult<()> {
+        writer.write_all(b"{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")
+    }
+
+    fn write_row(&mut self, writer: &mut dyn Write, row: &Row) -> IoResult<()> {
+        let pid = self.process_id(writer, row)?;
+        let tid = match row.kind {
+            RowKind::Marker => 0,
+            RowKind::Task | RowKind::Milestone => self.thread_id(writer, pid, row)?,
+        };
+        let start = row.start * MICROSECONDS_IN_MILLISECOND + row.start_micros;
+        let end = row.end * MICROSECONDS_IN_MILLISECOND + row.end_micros;
+        let (ph, s, dur) = match row.kind {
+            RowKind::Task => ("X", None, Some(end - start)),
+            RowKind::Milestone => ("i", Some("t"), None),
+            RowKind::Marker => ("i", Some("g"), None),
+        };
+        let event = Event {
+            name: row.name.clone(),
+            cat: Some(row.group.clone()),
+            ph,
+            s,
+            ts: Some(start),
+            dur,
+            pid,
+            tid,
+            args: Args {
+                name: None,
+                running: Some(row.running),
+                critical: Some(row.critical),
+            },
+        };
+
+        self.write_event(writer, &event)
+    }
+
+    fn write_quality(&mut self, _writer: &mut dyn Write, report: &QualityReport) -> IoResult<()> {
+        let metadata = QualityMetadata { quality: report };
+
+        self.metadata = Some(serde_json::to_string(&metadata).map_err(IoError::other)?);
+
+        Ok(())
+    }
+
+    fn write_footer(&mut self, writer: &mut dyn Write) -> IoResult<()> {
+        match &self.metadata {
+            Some(metadata) => writer.write_fmt(format_args!("],\"metadata\":{}}}", metadata)),
+            None => writer.write_all(b"]}"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Event {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cat: Option<String>,
+    ph: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ts: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<i64>,
+    pid: usize,
+    tid: usize,
+    args: Args,
+}
+
+impl Event {
+    fn metadata(kind: &str, pid: usize, tid: usize, name: String) -> Event {
+        Event {
+            name: kind.into(),
+            cat: None,
+            ph: "M",
+            s: None,
+            ts: None,
+            dur: None,
+            pid,
+            tid,
+            args: Args {
+                name: Some(name),
+                running: None,
+                critical: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Args {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    running: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    critical: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::DatabaseError;
+    use crate::database::IntervalPolicy;
+    use crate::database::Target;
+    use time::Timespec;
+
+    fn write(rows: &[Row]) -> serde_json::Value {
+        let mut writer = TraceWriter::new("stage", TraceProcess::Stage);
+        let mut output = Vec::new();
+
+        writer.write_header(&mut output).unwrap();
+
+        for row in rows {
+            writer.write_row(&mut output, row).unwrap();
+        }
+
+        writer.write_footer(&mut output).unwrap();
+        serde_json::from_slice(&output).unwrap()
+    }
+
+    fn target(start: Timespec, end: Timespec) -> Target {
+        Target {
+            name: "compile".into(),
+            start_time: start,
+            end_time: end,
+            group: "build".into(),
+            thread: "main".into(),
+            running: false,
+            id: None,
+            parent: None,
+        }
+    }
+
+    #[test]
+    fn tasks_keep_microseconds() {
+        let row = Row::from_target(&target(
+            Timespec::new(10, 1_250_999),
+            Timespec::new(10, 2_000_500),
+        ));
+        let trace = write(&[row]);
+        let event = &trace["traceEvents"][2];
+
+        assert_eq!(event["ph"], "X");
+        assert_eq!(event["ts"], 10_001_250);
+        assert_eq!(event["dur"], 750);
+    }
+
+    #[test]
+    fn sub_millisecond_targets_are_complete_events() {
+        let row = Row::from_target(&target(
+            Timespec::new(10, 1_250_000),
+            Timespec::new(10, 1_750_000),
+        ));
+        let trace = write(&[row]);
+        let event = &trace["traceEvents"][2];
+
+        assert_eq!(event["ph"], "X");
+        assert_eq!(event["ts"], 10_001_250);
+        assert_eq!(event["dur"], 500);
+    }
+
+    #[test]
+    fn equal_times_are_instant_events() {
+        let time = Timespec::new(10, 1_250_000);
+        let trace = write(&[Row::from_target(&target(time, time))]);
+        let event = &trace["traceEvents"][2];
+
+        assert_eq!(event["ph"], "i");
+        assert!(event.get("dur").is_none());
+    }
+
+    #[test]
+    fn processes_and_threads_are_named_once() {
+        let row = Row::from_target(&target(Timespec::new(10, 0), Timespec::new(11, 0)));
+        let trace = write(&[row.clone(), row]);
+        let events = trace["traceEvents"].as_array().unwrap();
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0]["args"]["name"], "stage");
+        assert_eq!(events[1]["args"]["name"], "main");
+        assert_eq!(events[3]["dur"], 1_000_000);
+    }
+
+    #[test]
+    fn quality_is_top_level_metadata() {
+        let mut report = QualityReport::new(IntervalPolicy::Clamp);
+        let mut writer = TraceWriter::new("stage", TraceProcess::Stage);
+        let mut output = Vec::new();
+
+        report
+            .add_error(4, DatabaseError::null_value("end"), 10)
+            .unwrap();
+        writer.write_header(&mut output).unwrap();
+        writer
+            .write_row(&mut output, &Row::task("a", 1, 2))
+            .unwrap();
+        writer.write_quality(&mut output, &report).unwrap();
+        writer.write_footer(&mut output).unwrap();
+
+        let value: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+        assert_eq!(value["traceEvents"].as_array().unwrap().len(), 3);
+        assert_eq!(value["metadata"]["quality"]["errors"][0]["position"], 4);
+        assert_eq!(value["metadata"]["quality"]["errors"][0]["column"], "end");
+    }
+}
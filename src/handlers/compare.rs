@@ -0,0 +1,59 @@
+use super::util;
+use super::ConnectionRequest;
+use super::HandlerError;
+use crate::config::Config;
+use crate::stats;
+use iron::middleware::Handler;
+use iron::IronResult;
+use iron::Request as IronRequest;
+use iron::Response as IronResponse;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct CompareHandler {
+    config: Arc<Config>,
+}
+
+impl CompareHandler {
+    pub fn new(config: &Arc<Config>) -> CompareHandler {
+        CompareHandler {
+            config: config.clone(),
+        }
+    }
+}
+
+impl Handler for CompareHandler {
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        util::handle_request(request, move |request: Request| {
+            let postgres = request.connection.postgres(&self.config)?;
+            let base_database = request
+                .connection
+                .database(&self.config, &request.base.database)?;
+            let current_database = request
+                .connection
+                .database(&self.config, &request.current.database)?;
+
+            stats::compare(
+                &postgres,
+                (&base_database, &request.base.stage),
+                (&current_database, &request.current.stage),
+            )
+            .map_err(HandlerError::database_error)
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    base: StageRequest,
+    current: StageRequest,
+}
+
+#[derive(Debug, Deserialize)]
+struct StageRequest {
+    #[serde(default)]
+    database: Option<String>,
+    stage: String,
+}
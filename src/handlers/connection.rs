@@ -0,0 +1,113 @@
+use super::HandlerError;
+use super::HandlerResult;
+use crate::config::Config;
+use crate::database::IntervalPolicy;
+use crate::database::PostgreSQL;
+use crate::database::SourceMapping;
+
+/// Connection part of request body. Either `profile` name from configuration or direct
+/// credentials, the latter only when allowed by configuration. `invalid_intervals` and
+/// `max_row_errors` override settings of the mapping.
+#[derive(Debug, Deserialize)]
+pub struct ConnectionRequest {
+    #[serde(default)]
+    profile: Option<String>,
+    #[serde(default)]
+    server: Option<String>,
+    #[serde(default)]
+    port: Option<u16>,
+    #[serde(default)]
+    user: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    mapping: Option<SourceMapping>,
+    #[serde(default)]
+    invalid_intervals: Option<IntervalPolicy>,
+    #[serde(default)]
+    max_row_errors: Option<usize>,
+}
+
+const DEFAULT_PORT: u16 = 5432;
+
+impl ConnectionRequest {
+    pub fn postgres(&self, config: &Config) -> HandlerResult<PostgreSQL> {
+        let settings = config.database();
+
+        self.connect(config).map(|postgres| {
+            let mut postgres = postgres
+                .with_fetch_size(settings.fetch_size)
+                .with_default_database(&settings.default_database);
+
+            if let Some(policy) = self.invalid_intervals {
+                postgres = postgres.with_interval_policy(policy);
+            }
+
+            if let Some(max_row_errors) = self.max_row_errors {
+                postgres = postgres.with_max_row_errors(max_row_errors);
+            }
+
+            postgres
+        })
+    }
+
+    fn connect(&self, config: &Config) -> HandlerResult<PostgreSQL> {
+        if let Some(name) = &self.profile {
+            return config
+                .profile(name)
+                .map(|profile| profile.postgres())
+                .ok_or_else(|| HandlerError::unknown_profile(name));
+        }
+
+        if !config.allow_credentials() {
+            return Err(HandlerError::credentials_disabled());
+        }
+
+        match (&self.server, &self.user) {
+            (Some(server), Some(user)) => {
+                let port = self.port.unwrap_or(DEFAULT_PORT);
+                let password = self.password.as_deref().unwrap_or("");
+                let postgres = PostgreSQL::new(server, port, user, password);
+
+                match &self.mapping {
+                    Some(mapping) => Ok(postgres.with_mapping(mapping)),
+                    None => Ok(postgres),
+                }
+            }
+            _ => Err(HandlerError::new(
+                "Either profile or server and user required",
+            )),
+        }
+    }
+
+    /// Rejects direct credentials, used for requests sent in URL where only `profile` is allowed,
+    /// so passwords do not end up in logs and browser history.
+    pub fn require_profile(&self) -> HandlerResult<()> {
+        let credentials = self.server.is_some()
+            || self.port.is_some()
+            || self.user.is_some()
+            || self.password.is_some();
+
+        if credentials || self.profile.is_none() {
+            return Err(HandlerError::credentials_disabled());
+        }
+
+        Ok(())
+    }
+
+    /// Returns requested database or default database of the profile.
+    pub fn database(&self, config: &Config, database: &Option<String>) -> HandlerResult<String> {
+        let profile_database = self
+            .profile
+            .as_ref()
+            .and_then(|name| config.profile(name))
+            .and_then(|profile| profile.database());
+
+        database
+            .as_ref()
+            .map(String::as_str)
+            .or(profile_database)
+            .map(String::from)
+            .ok_or_else(|| HandlerError::new("Database name required"))
+    }
+}
@@ -2,31 +2,41 @@ mod tempfile;
 
 use self::tempfile::TemporaryFile;
 use super::util;
+use super::ConnectionRequest;
 use super::HandlerError;
-use crate::database::PostgreSQL;
+use crate::config::Config;
+use crate::database::QualityReport;
+use crate::export::read_events;
+use crate::export::ExportOptions;
+use crate::export::Format;
+use crate::export::Nesting;
+use crate::export::Row;
+use crate::export::TraceProcess;
+use crate::stats;
 use iron::middleware::Handler;
+use iron::modifier::Modifier;
 use iron::response::BodyReader;
 use iron::IronResult;
 use iron::Request as IronRequest;
 use iron::Response as IronResponse;
 use std::io::BufWriter;
-use std::io::Result as IoResult;
-use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
-use time::Timespec;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct DataHandler {
+    config: Arc<Config>,
     temp_dir: PathBuf,
     request_index: AtomicUsize,
 }
 
 impl DataHandler {
-    pub fn new(temp_dir: &Path) -> DataHandler {
+    pub fn new(config: &Arc<Config>, temp_dir: &Path) -> DataHandler {
         DataHandler {
+            config: config.clone(),
             temp_dir: temp_dir.to_path_buf(),
             request_index: AtomicUsize::new(0),
         }
@@ -40,86 +50,127 @@ impl DataHandler {
     }
 }
 
-const SECOND_MULTIPLIER: i64 = 1_000;
-const NANOSECOND_DIVIDER: i64 = 1_000_000;
-
 impl Handler for DataHandler {
     fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        let accept_format = util::accept_format(request);
+
         util::handle_read(request, move |request: Request| {
-            let postgres = PostgreSQL::new(
-                &request.server,
-                request.port,
-                &request.user,
-                &request.password,
-            );
+            let postgres = request.connection.postgres(&self.config)?;
+            let database = request
+                .connection
+                .database(&self.config, &request.database)?;
+            let format = request.format.or(accept_format).unwrap_or(Format::Dlm);
+            let options = ExportOptions {
+                stage: request.stage.clone(),
+                trace_process: request.trace_process,
+            };
+            let mut row_writer = format.writer(&options);
             let path = self.next_temporary_path();
-            let file = TemporaryFile::new(&path).map_err(|e| HandlerError::new(&e.to_string()))?;
+            let file = TemporaryFile::new(&path).map_err(HandlerError::io_error)?;
             let mut writer = BufWriter::new(file);
 
-            postgres
-                .data(
-                    &request.database,
-                    &request.stage,
-                    |name, start_date, end_date, group, thread| {
-                        write_dlm_string(&mut writer, name, start_date, end_date, group, thread)
-                    },
-                )
-                .map_err(|e| HandlerError::new(&e.to_string()))?
-                .map_err(|e| HandlerError::new(&e.to_string()))?;
+            row_writer
+                .write_header(&mut writer)
+                .map_err(HandlerError::io_error)?;
+
+            let quality = if request.critical_path {
+                let rows = stats::critical_rows(&postgres, &database, &request.stage)
+                    .map_err(HandlerError::database_error)?;
+
+                for row in &rows {
+                    row_writer
+                        .write_row(&mut writer, row)
+                        .map_err(HandlerError::io_error)?;
+                }
+
+                None
+            } else {
+                let mut nesting = Nesting::new();
+                let mut write = |row: Row| row_writer.write_row(&mut writer, &row);
+                let report = postgres
+                    .data(&database, &request.stage, |target| {
+                        nesting.push(Row::from_target(target), &mut write)
+                    })
+                    .map_err(HandlerError::database_error)?
+                    .map_err(HandlerError::io_error)?;
+
+                nesting.finish(&mut write).map_err(HandlerError::io_error)?;
+
+                Some(report)
+            };
+
+            let events = read_events(&postgres, &database, &request.stage)
+                .map_err(HandlerError::database_error)?;
+
+            for row in &events {
+                row_writer
+                    .write_row(&mut writer, row)
+                    .map_err(HandlerError::io_error)?;
+            }
+
+            if let Some(report) = quality.as_ref().filter(|report| !report.errors.is_empty()) {
+                row_writer
+                    .write_quality(&mut writer, report)
+                    .map_err(HandlerError::io_error)?;
+            }
+
+            row_writer
+                .write_footer(&mut writer)
+                .map_err(HandlerError::io_error)?;
 
             let mut file = writer
                 .into_inner()
-                .map_err(|e| HandlerError::new(&e.to_string()))?;
+                .map_err(|e| HandlerError::io_error(e.into_error()))?;
 
-            file.seek_from_start(0)
-                .map_err(|e| HandlerError::new(&e.to_string()))?;
+            file.seek_from_start(0).map_err(HandlerError::io_error)?;
 
-            Ok(BodyReader(file))
+            Ok((
+                format.content_type(),
+                QualityHeaders(quality),
+                BodyReader(file),
+            ))
         })
     }
 }
 
-fn write_dlm_string(
-    writer: &mut dyn Write,
-    name: &str,
-    start_date: Timespec,
-    end_date: Timespec,
-    group: &str,
-    thread: &str,
-) -> IoResult<()> {
-    let name: String = name
-        .chars()
-        .filter(|&ch| !ch.is_control() && ch != ';')
-        .collect();
-    let start_date: i64 =
-        SECOND_MULTIPLIER * start_date.sec + start_date.nsec as i64 / NANOSECOND_DIVIDER;
-    let end_date: i64 =
-        SECOND_MULTIPLIER * end_date.sec + end_date.nsec as i64 / NANOSECOND_DIVIDER;
-
-    if start_date < end_date {
-        writer.write_fmt(format_args!(
-            "{};{};{};{};{}\n",
-            name, start_date, end_date, group, thread
-        ))
-    } else {
-        // If start time greater then end time assume that end time invalid.
-        writer.write_fmt(format_args!(
-            "{};{};{};{};{}\n",
-            name,
-            start_date,
-            start_date + 1,
-            group,
-            thread
-        ))
+/// Sets data quality counters as response headers. Row errors are written in the body after rows
+/// because values may contain characters not allowed in headers.
+struct QualityHeaders(Option<QualityReport>);
+
+impl Modifier<IronResponse> for QualityHeaders {
+    fn modify(self, response: &mut IronResponse) {
+        let report = match self.0 {
+            Some(report) => report,
+            None => return,
+        };
+        let mut set = |name: &str, value: String| {
+            response
+                .headers
+                .set_raw(name.to_owned(), vec![value.into_bytes()]);
+        };
+
+        set("X-Quality-Policy", report.policy.name().into());
+        set("X-Quality-Rows", report.rows.to_string());
+        set("X-Quality-Invalid", report.invalid().to_string());
+        set("X-Quality-Clamped", report.clamped.to_string());
+        set("X-Quality-Dropped", report.dropped.to_string());
+        set("X-Quality-Swapped", report.swapped.to_string());
+        set("X-Quality-Milestones", report.milestones.to_string());
+        set("X-Quality-Unparsable", report.unparsable.to_string());
     }
 }
 
 #[derive(Debug, Deserialize)]
 struct Request {
-    server: String,
-    port: u16,
-    user: String,
-    password: String,
-    database: String,
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    #[serde(default)]
+    database: Option<String>,
     stage: String,
+    #[serde(default)]
+    format: Option<Format>,
+    #[serde(default)]
+    trace_process: TraceProcess,
+    #[serde(default)]
+    critical_path: bool,
 }
@@ -27,7 +27,7 @@ impl TemporaryFile {
             .write(true)
             .truncate(true)
             .create(true)
-            .open(&path)?;
+            .open(path)?;
 
         Ok(TemporaryFile {
             path: path.to_path_buf(),
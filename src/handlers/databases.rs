@@ -1,41 +1,84 @@
 use super::util;
+use super::ConnectionRequest;
 use super::HandlerError;
-use crate::database::PostgreSQL;
+use crate::config::Config;
 use iron::middleware::Handler;
 use iron::IronResult;
 use iron::Request as IronRequest;
 use iron::Response as IronResponse;
+use std::sync::Arc;
 
 #[derive(Debug)]
-pub struct DatabasesHandler {}
+pub struct DatabasesHandler {
+    config: Arc<Config>,
+}
 
 impl DatabasesHandler {
-    pub fn new() -> DatabasesHandler {
-        DatabasesHandler {}
+    pub fn new(config: &Arc<Config>) -> DatabasesHandler {
+        DatabasesHandler {
+            config: config.clone(),
+        }
     }
 }
 
 impl Handler for DatabasesHandler {
     fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
         util::handle_request(request, move |request: Request| {
-            let postgres = PostgreSQL::new(
-                &request.server,
-                request.port,
-                &request.user,
-                &request.password,
-            );
-
-            postgres
+            let postgres = request.connection.postgres(&self.config)?;
+            let names = postgres
                 .database_names()
-                .map_err(|e| HandlerError::new(&e.to_string()))
+                .map_err(HandlerError::database_error)?;
+            let check = match request.check {
+                Some(check) => check,
+                None => return Ok(Response::Names(names)),
+            };
+
+            // Database which can not be connected or read with the mapping has no data for us.
+            let databases = names.into_iter().map(|name| {
+                let gantt = postgres.has_data(&name).unwrap_or(false);
+
+                Database { name, gantt }
+            });
+
+            match check {
+                DatabaseCheck::Mark => Ok(Response::Databases(databases.collect())),
+                DatabaseCheck::Filter => Ok(Response::Names(
+                    databases
+                        .filter(|database| database.gantt)
+                        .map(|database| database.name)
+                        .collect(),
+                )),
+            }
         })
     }
 }
 
+/// Checks every database for mapped table with rows. `Mark` returns all databases with `gantt`
+/// flag, `Filter` returns names of databases with data only.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DatabaseCheck {
+    Mark,
+    Filter,
+}
+
 #[derive(Debug, Deserialize)]
 struct Request {
-    server: String,
-    port: u16,
-    user: String,
-    password: String,
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    #[serde(default)]
+    check: Option<DatabaseCheck>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum Response {
+    Names(Vec<String>),
+    Databases(Vec<Database>),
+}
+
+#[derive(Debug, Serialize)]
+struct Database {
+    name: String,
+    gantt: bool,
 }
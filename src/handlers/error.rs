@@ -1,27 +1,237 @@
+use crate::database::DatabaseError;
+use crate::render::RenderError;
+use iron::status;
+use iron::status::Status;
 use std::error::Error;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
+use std::io::Error as IoError;
 
 pub type HandlerResult<T> = Result<T, HandlerError>;
 
 #[derive(Debug)]
-pub struct HandlerError {
-    message: String,
+pub enum HandlerError {
+    InvalidRequest { message: String },
+    UnknownProfile { name: String },
+    CredentialsDisabled,
+    TooManyStreams { limit: usize },
+    DatabaseError { error: DatabaseError },
+    RenderError { error: RenderError },
+    IoError { message: String },
+}
+
+/// Stable machine-readable error code sent to API clients together with the message.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidRequest,
+    UnknownProfile,
+    CredentialsDisabled,
+    ConnectionRefused,
+    ConnectionFailed,
+    AuthFailed,
+    DatabaseMissing,
+    SchemaMissing,
+    TableMissing,
+    InvalidMapping,
+    Timeout,
+    QueryFailed,
+    TimeParse,
+    InvalidData,
+    InvalidRenderOptions,
+    RenderFailed,
+    TooManyStreams,
+    Internal,
 }
 
 impl HandlerError {
     pub fn new(message: &str) -> HandlerError {
-        HandlerError {
+        HandlerError::InvalidRequest {
             message: message.into(),
         }
     }
+
+    pub fn unknown_profile(name: &str) -> HandlerError {
+        HandlerError::UnknownProfile { name: name.into() }
+    }
+
+    pub fn credentials_disabled() -> HandlerError {
+        HandlerError::CredentialsDisabled
+    }
+
+    pub fn too_many_streams(limit: usize) -> HandlerError {
+        HandlerError::TooManyStreams { limit }
+    }
+
+    pub fn database_error(error: DatabaseError) -> HandlerError {
+        HandlerError::DatabaseError { error }
+    }
+
+    pub fn render_error(error: RenderError) -> HandlerError {
+        HandlerError::RenderError { error }
+    }
+
+    pub fn io_error(error: IoError) -> HandlerError {
+        warn!("Failed to write response: {}", error);
+
+        HandlerError::IoError {
+            message: format!("{}", error),
+        }
+    }
+
+    /// Error code, database errors are split by kind so clients can tell unavailable server from
+    /// wrong credentials, mapping or bad data.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            HandlerError::InvalidRequest { .. } => ErrorCode::InvalidRequest,
+            HandlerError::UnknownProfile { .. } => ErrorCode::UnknownProfile,
+            HandlerError::CredentialsDisabled => ErrorCode::CredentialsDisabled,
+            HandlerError::TooManyStreams { .. } => ErrorCode::TooManyStreams,
+            HandlerError::IoError { .. } => ErrorCode::Internal,
+            HandlerError::RenderError { error } => match error {
+                RenderError::InvalidSize { .. }
+                | RenderError::InvalidTimeWindow { .. }
+                | RenderError::InvalidDpi { .. }
+                | RenderError::ImageNotCreated { .. } => ErrorCode::InvalidRenderOptions,
+                RenderError::FontNotLoaded { .. } | RenderError::EncodeError { .. } => {
+                    ErrorCode::RenderFailed
+                }
+            },
+            HandlerError::DatabaseError { error } => match error {
+                DatabaseError::ConnectionRefused { .. } => ErrorCode::ConnectionRefused,
+                DatabaseError::ConnectionError { .. } => ErrorCode::ConnectionFailed,
+                DatabaseError::AuthenticationFailed { .. } => ErrorCode::AuthFailed,
+                DatabaseError::DatabaseNotExists { .. } => ErrorCode::DatabaseMissing,
+                DatabaseError::SchemaNotExists { .. } => ErrorCode::SchemaMissing,
+                DatabaseError::TableNotExists { .. } => ErrorCode::TableMissing,
+                DatabaseError::MappedColumnNotExists { .. }
+                | DatabaseError::InvalidTimeZone { .. }
+                | DatabaseError::DependenciesNotMapped => ErrorCode::InvalidMapping,
+                DatabaseError::Timeout { .. } => ErrorCode::Timeout,
+                DatabaseError::QueryExecutionError { .. }
+                | DatabaseError::PrepareQueryError { .. }
+                | DatabaseError::TransactionError { .. }
+                | DatabaseError::ColumnNotExists => ErrorCode::QueryFailed,
+                DatabaseError::TimeParseError { .. } => ErrorCode::TimeParse,
+                DatabaseError::ConversionError { .. }
+                | DatabaseError::NullValue { .. }
+                | DatabaseError::InvalidInterval { .. }
+                | DatabaseError::TooManyRowErrors { .. } => ErrorCode::InvalidData,
+            },
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        self.code().status()
+    }
+
+    /// Original database error, sent to clients as error details.
+    pub fn details(&self) -> Option<&DatabaseError> {
+        match self {
+            HandlerError::DatabaseError { error } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl ErrorCode {
+    /// HTTP status of the response, 4xx for errors of request or data, 5xx for failures of
+    /// database server or the application.
+    pub fn status(self) -> Status {
+        match self {
+            ErrorCode::InvalidRequest | ErrorCode::InvalidRenderOptions => status::BadRequest,
+            ErrorCode::CredentialsDisabled | ErrorCode::AuthFailed => status::Forbidden,
+            ErrorCode::UnknownProfile
+            | ErrorCode::DatabaseMissing
+            | ErrorCode::SchemaMissing
+            | ErrorCode::TableMissing => status::NotFound,
+            ErrorCode::InvalidMapping | ErrorCode::TimeParse | ErrorCode::InvalidData => {
+                status::UnprocessableEntity
+            }
+            ErrorCode::ConnectionRefused | ErrorCode::ConnectionFailed => status::BadGateway,
+            ErrorCode::Timeout => status::GatewayTimeout,
+            ErrorCode::TooManyStreams => status::ServiceUnavailable,
+            ErrorCode::QueryFailed | ErrorCode::RenderFailed | ErrorCode::Internal => {
+                status::InternalServerError
+            }
+        }
+    }
 }
 
 impl Error for HandlerError {}
 
 impl Display for HandlerError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "{}", self.message)
+        match self {
+            HandlerError::InvalidRequest { message } => write!(f, "{}", message),
+            HandlerError::UnknownProfile { name } => write!(f, "Unknown profile `{}`", name),
+            HandlerError::CredentialsDisabled => write!(
+                f,
+                "Connection profile required, direct credentials are disabled"
+            ),
+            HandlerError::TooManyStreams { limit } => {
+                write!(f, "Too many open streams, the limit is {}", limit)
+            }
+            HandlerError::DatabaseError { error } => write!(f, "{}", error),
+            HandlerError::RenderError { error } => write!(f, "{}", error),
+            HandlerError::IoError { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_have_fixed_status() {
+        let cases = [
+            (ErrorCode::InvalidRequest, "invalid_request", 400),
+            (
+                ErrorCode::InvalidRenderOptions,
+                "invalid_render_options",
+                400,
+            ),
+            (ErrorCode::CredentialsDisabled, "credentials_disabled", 403),
+            (ErrorCode::AuthFailed, "auth_failed", 403),
+            (ErrorCode::UnknownProfile, "unknown_profile", 404),
+            (ErrorCode::DatabaseMissing, "database_missing", 404),
+            (ErrorCode::SchemaMissing, "schema_missing", 404),
+            (ErrorCode::TableMissing, "table_missing", 404),
+            (ErrorCode::InvalidMapping, "invalid_mapping", 422),
+            (ErrorCode::TimeParse, "time_parse", 422),
+            (ErrorCode::InvalidData, "invalid_data", 422),
+            (ErrorCode::ConnectionRefused, "connection_refused", 502),
+            (ErrorCode::ConnectionFailed, "connection_failed", 502),
+            (ErrorCode::TooManyStreams, "too_many_streams", 503),
+            (ErrorCode::Timeout, "timeout", 504),
+            (ErrorCode::QueryFailed, "query_failed", 500),
+            (ErrorCode::RenderFailed, "render_failed", 500),
+            (ErrorCode::Internal, "internal", 500),
+        ];
+
+        for &(code, name, status) in &cases {
+            assert_eq!(serde_json::to_value(code).unwrap(), name);
+            assert_eq!(code.status().to_u16(), status, "{}", name);
+        }
+    }
+
+    #[test]
+    fn oversized_image_is_bad_request() {
+        let cases = [
+            (RenderError::image_not_created(100_000, 100_000), 400),
+            (RenderError::invalid_dpi(72, 600), 400),
+            (
+                RenderError::EncodeError {
+                    message: "broken".into(),
+                },
+                500,
+            ),
+        ];
+
+        for (error, status) in cases {
+            assert_eq!(HandlerError::render_error(error).status().to_u16(), status);
+        }
     }
 }
@@ -0,0 +1,66 @@
+use super::util;
+use super::ConnectionRequest;
+use super::HandlerError;
+use crate::config::Config;
+use crate::stats;
+use crate::stats::InferenceScope;
+use iron::middleware::Handler;
+use iron::IronResult;
+use iron::Request as IronRequest;
+use iron::Response as IronResponse;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct InferredPathHandler {
+    config: Arc<Config>,
+}
+
+impl InferredPathHandler {
+    pub fn new(config: &Arc<Config>) -> InferredPathHandler {
+        InferredPathHandler {
+            config: config.clone(),
+        }
+    }
+}
+
+impl Handler for InferredPathHandler {
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        util::handle_request(request, move |request: Request| {
+            let tolerance = request.tolerance.unwrap_or(stats::DEFAULT_TOLERANCE);
+
+            if !(0..=stats::MAX_TOLERANCE).contains(&tolerance) {
+                return Err(HandlerError::new(&format!(
+                    "Tolerance must be between 0 and {} milliseconds",
+                    stats::MAX_TOLERANCE
+                )));
+            }
+
+            let postgres = request.connection.postgres(&self.config)?;
+            let database = request
+                .connection
+                .database(&self.config, &request.database)?;
+
+            stats::inferred_path(
+                &postgres,
+                &database,
+                &request.stage,
+                tolerance,
+                request.scope,
+            )
+            .map_err(HandlerError::database_error)
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    #[serde(default)]
+    database: Option<String>,
+    stage: String,
+    #[serde(default)]
+    tolerance: Option<i64>,
+    #[serde(default)]
+    scope: InferenceScope,
+}
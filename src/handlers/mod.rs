@@ -1,13 +1,39 @@
-mod databases;
+mod compare;
+mod connection;
+mod critical_path;
 mod data;
+mod databases;
 mod error;
+mod inferred_path;
+mod profiles;
+mod quality;
+mod render;
+mod schema;
+mod simulate;
 mod stages;
+mod stats;
+mod tail;
+mod trend;
 mod util;
+mod utilization;
 
+pub use self::compare::CompareHandler;
+pub use self::connection::ConnectionRequest;
+pub use self::critical_path::CriticalPathHandler;
 pub use self::data::DataHandler;
 pub use self::databases::DatabasesHandler;
 pub use self::error::HandlerError;
 pub use self::error::HandlerResult;
+pub use self::inferred_path::InferredPathHandler;
+pub use self::profiles::ProfilesHandler;
+pub use self::quality::QualityHandler;
+pub use self::render::PngHandler;
+pub use self::render::SvgHandler;
+pub use self::schema::SchemaHandler;
+pub use self::simulate::SimulateHandler;
 pub use self::stages::StagesHandler;
-pub use self::util::handle_read;
-pub use self::util::handle_request;
+pub use self::stats::StatsHandler;
+pub use self::tail::StreamHandler;
+pub use self::tail::TailHandler;
+pub use self::trend::TrendHandler;
+pub use self::utilization::UtilizationHandler;
@@ -0,0 +1,40 @@
+use super::util;
+use crate::config::Config;
+use iron::middleware::Handler;
+use iron::IronResult;
+use iron::Request as IronRequest;
+use iron::Response as IronResponse;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct ProfilesHandler {
+    config: Arc<Config>,
+}
+
+impl ProfilesHandler {
+    pub fn new(config: &Arc<Config>) -> ProfilesHandler {
+        ProfilesHandler {
+            config: config.clone(),
+        }
+    }
+}
+
+impl Handler for ProfilesHandler {
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        util::handle_request(request, move |_: Request| {
+            Ok(Response {
+                profiles: self.config.profile_names(),
+                allow_credentials: self.config.allow_credentials(),
+            })
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    profiles: Vec<String>,
+    allow_credentials: bool,
+}
@@ -0,0 +1,105 @@
+use super::util;
+use super::ConnectionRequest;
+use super::HandlerError;
+use super::HandlerResult;
+use crate::config::Config;
+use crate::export;
+use crate::export::Row;
+use crate::render;
+use crate::render::Font;
+use crate::render::RenderOptions;
+use iron::middleware::Handler;
+use iron::mime::Mime;
+use iron::mime::SubLevel;
+use iron::mime::TopLevel;
+use iron::IronResult;
+use iron::Request as IronRequest;
+use iron::Response as IronResponse;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct SvgHandler {
+    config: Arc<Config>,
+}
+
+impl SvgHandler {
+    pub fn new(config: &Arc<Config>) -> SvgHandler {
+        SvgHandler {
+            config: config.clone(),
+        }
+    }
+}
+
+impl Handler for SvgHandler {
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        util::handle_read(request, move |request: Request| {
+            let rows = read_rows(&self.config, &request)?;
+            let image =
+                render::render_svg(&rows, &request.options).map_err(HandlerError::render_error)?;
+            let content_type = Mime(TopLevel::Image, SubLevel::Ext("svg+xml".into()), vec![]);
+
+            Ok((content_type, image))
+        })
+    }
+}
+
+/// Renders with the font loaded at startup.
+#[derive(Debug)]
+pub struct PngHandler {
+    config: Arc<Config>,
+    font: Font,
+}
+
+impl PngHandler {
+    pub fn new(config: &Arc<Config>, font: Font) -> PngHandler {
+        PngHandler {
+            config: config.clone(),
+            font,
+        }
+    }
+}
+
+impl Handler for PngHandler {
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        util::handle_read(request, move |request: Request| {
+            let rows = read_rows(&self.config, &request)?;
+            let image = render::render_png(&rows, &request.options, &self.font)
+                .map_err(HandlerError::render_error)?;
+            let content_type = Mime(TopLevel::Image, SubLevel::Png, vec![]);
+
+            Ok((content_type, image))
+        })
+    }
+}
+
+/// Checks image settings before querying database and reads all stage rows and events.
+fn read_rows(config: &Config, request: &Request) -> HandlerResult<Vec<Row>> {
+    request
+        .options
+        .validate()
+        .map_err(HandlerError::render_error)?;
+
+    let postgres = request.connection.postgres(config)?;
+    let database = request.connection.database(config, &request.database)?;
+
+    let mut rows = export::read_rows(&postgres, &database, &request.stage)
+        .map_err(HandlerError::database_error)?;
+
+    rows.extend(
+        export::read_events(&postgres, &database, &request.stage)
+            .map_err(HandlerError::database_error)?,
+    );
+
+    Ok(rows)
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    #[serde(default)]
+    database: Option<String>,
+    stage: String,
+    #[serde(flatten)]
+    options: RenderOptions,
+}
@@ -0,0 +1,45 @@
+use super::util;
+use super::ConnectionRequest;
+use super::HandlerError;
+use crate::config::Config;
+use iron::middleware::Handler;
+use iron::IronResult;
+use iron::Request as IronRequest;
+use iron::Response as IronResponse;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct SchemaHandler {
+    config: Arc<Config>,
+}
+
+impl SchemaHandler {
+    pub fn new(config: &Arc<Config>) -> SchemaHandler {
+        SchemaHandler {
+            config: config.clone(),
+        }
+    }
+}
+
+impl Handler for SchemaHandler {
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        util::handle_request(request, move |request: Request| {
+            let postgres = request.connection.postgres(&self.config)?;
+            let database = request
+                .connection
+                .database(&self.config, &request.database)?;
+
+            postgres
+                .inspect(&database)
+                .map_err(HandlerError::database_error)
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    #[serde(default)]
+    database: Option<String>,
+}
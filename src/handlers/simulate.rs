@@ -0,0 +1,63 @@
+use super::util;
+use super::ConnectionRequest;
+use super::HandlerError;
+use crate::config::Config;
+use crate::stats;
+use crate::stats::SchedulingPolicy;
+use iron::middleware::Handler;
+use iron::IronResult;
+use iron::Request as IronRequest;
+use iron::Response as IronResponse;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct SimulateHandler {
+    config: Arc<Config>,
+}
+
+impl SimulateHandler {
+    pub fn new(config: &Arc<Config>) -> SimulateHandler {
+        SimulateHandler {
+            config: config.clone(),
+        }
+    }
+}
+
+impl Handler for SimulateHandler {
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        util::handle_request(request, move |request: Request| {
+            if request.workers == 0 || request.workers > stats::MAX_WORKERS {
+                return Err(HandlerError::new(&format!(
+                    "Number of workers must be between 1 and {}",
+                    stats::MAX_WORKERS
+                )));
+            }
+
+            let postgres = request.connection.postgres(&self.config)?;
+            let database = request
+                .connection
+                .database(&self.config, &request.database)?;
+
+            stats::simulate(
+                &postgres,
+                &database,
+                &request.stage,
+                request.workers,
+                request.policy,
+            )
+            .map_err(HandlerError::database_error)
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    #[serde(default)]
+    database: Option<String>,
+    stage: String,
+    workers: usize,
+    #[serde(default)]
+    policy: SchedulingPolicy,
+}
@@ -1,42 +1,122 @@
 use super::util;
+use super::ConnectionRequest;
 use super::HandlerError;
-use crate::database::PostgreSQL;
+use crate::config::Config;
+use crate::database::Stage;
+use crate::database::StageOrder;
+use crate::database::StageQuery;
+use crate::export;
 use iron::middleware::Handler;
 use iron::IronResult;
 use iron::Request as IronRequest;
 use iron::Response as IronResponse;
+use std::sync::Arc;
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 10_000;
 
 #[derive(Debug)]
-pub struct StagesHandler {}
+pub struct StagesHandler {
+    config: Arc<Config>,
+}
 
 impl StagesHandler {
-    pub fn new() -> StagesHandler {
-        StagesHandler {}
+    pub fn new(config: &Arc<Config>) -> StagesHandler {
+        StagesHandler {
+            config: config.clone(),
+        }
     }
 }
 
 impl Handler for StagesHandler {
     fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
         util::handle_request(request, move |request: Request| {
-            let postgres = PostgreSQL::new(
-                &request.server,
-                request.port,
-                &request.user,
-                &request.password,
-            );
-
-            postgres
-                .stage_names(&request.database)
-                .map_err(|e| HandlerError::new(&e.to_string()))
+            if !(1..=MAX_LIMIT).contains(&request.limit) {
+                return Err(HandlerError::new(&format!(
+                    "Limit must be between 1 and {}",
+                    MAX_LIMIT
+                )));
+            }
+
+            if request.offset < 0 {
+                return Err(HandlerError::new("Offset must not be negative"));
+            }
+
+            let postgres = request.connection.postgres(&self.config)?;
+            let database = request
+                .connection
+                .database(&self.config, &request.database)?;
+            let query = StageQuery {
+                filter: request.filter.filter(|filter| !filter.is_empty()),
+                order: request.order,
+                limit: Some(request.limit),
+                offset: request.offset,
+            };
+            let page = postgres
+                .stages(&database, &query)
+                .map_err(HandlerError::database_error)?;
+
+            Ok(Response {
+                stages: page.stages.iter().map(StageResponse::from_stage).collect(),
+                total: page.total,
+            })
         })
     }
 }
 
 #[derive(Debug, Deserialize)]
 struct Request {
-    server: String,
-    port: u16,
-    user: String,
-    password: String,
-    database: String,
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    #[serde(default)]
+    database: Option<String>,
+    #[serde(default)]
+    filter: Option<String>,
+    #[serde(default)]
+    order: StageOrder,
+    #[serde(default = "default_limit")]
+    limit: i64,
+    #[serde(default)]
+    offset: i64,
+}
+
+fn default_limit() -> i64 {
+    DEFAULT_LIMIT
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    stages: Vec<StageResponse>,
+    total: i64,
+}
+
+/// Stage summary, times are milliseconds since epoch, duration is wall-clock time in milliseconds.
+#[derive(Debug, Serialize)]
+struct StageResponse {
+    name: String,
+    start: i64,
+    end: Option<i64>,
+    duration: Option<i64>,
+    targets: i64,
+    groups: i64,
+    threads: i64,
+    running: bool,
+}
+
+impl StageResponse {
+    fn from_stage(stage: &Stage) -> StageResponse {
+        let start = export::to_millis(stage.start_time);
+        let end = stage.end_time.map(export::to_millis);
+
+        StageResponse {
+            name: stage.name.clone(),
+            start,
+            end,
+            duration: end.map(|end| end - start),
+            targets: stage.targets,
+            groups: stage.groups,
+            threads: stage.threads,
+            running: stage.running,
+        }
+    }
 }
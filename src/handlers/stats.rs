@@ -0,0 +1,57 @@
+use super::util;
+use super::ConnectionRequest;
+use super::HandlerError;
+use crate::config::Config;
+use crate::stats;
+use iron::middleware::Handler;
+use iron::IronResult;
+use iron::Request as IronRequest;
+use iron::Response as IronResponse;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct StatsHandler {
+    config: Arc<Config>,
+}
+
+impl StatsHandler {
+    pub fn new(config: &Arc<Config>) -> StatsHandler {
+        StatsHandler {
+            config: config.clone(),
+        }
+    }
+}
+
+impl Handler for StatsHandler {
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        util::handle_request(request, move |request: Request| {
+            let longest = request.longest.unwrap_or(stats::DEFAULT_LONGEST);
+
+            if longest > stats::MAX_LONGEST {
+                return Err(HandlerError::new(&format!(
+                    "Number of longest targets must not exceed {}",
+                    stats::MAX_LONGEST
+                )));
+            }
+
+            let postgres = request.connection.postgres(&self.config)?;
+            let database = request
+                .connection
+                .database(&self.config, &request.database)?;
+
+            stats::collect(&postgres, &database, &request.stage, longest)
+                .map_err(HandlerError::database_error)
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    #[serde(default)]
+    database: Option<String>,
+    stage: String,
+    #[serde(default)]
+    longest: Option<usize>,
+}
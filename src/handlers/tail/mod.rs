@@ -0,0 +1,127 @@
+mod stream;
+
+pub use self::stream::StreamHandler;
+
+use super::util;
+use super::ConnectionRequest;
+use super::HandlerError;
+use super::HandlerResult;
+use crate::config::Config;
+use crate::database::PostgreSQL;
+use crate::export::Nesting;
+use crate::export::Row;
+use iron::middleware::Handler;
+use iron::IronResult;
+use iron::Request as IronRequest;
+use iron::Response as IronResponse;
+use postgres::Connection;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct TailHandler {
+    config: Arc<Config>,
+}
+
+impl TailHandler {
+    pub fn new(config: &Arc<Config>) -> TailHandler {
+        TailHandler {
+            config: config.clone(),
+        }
+    }
+}
+
+impl Handler for TailHandler {
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        util::handle_request(request, move |request: Request| {
+            let postgres = request.connection.postgres(&self.config)?;
+            let database = request
+                .connection
+                .database(&self.config, &request.database)?;
+
+            let connection = postgres
+                .connection(&database)
+                .map_err(HandlerError::database_error)?;
+
+            fetch_update(&postgres, &connection, &request.stage, request.cursor)
+        })
+    }
+}
+
+/// Reads targets changed after `cursor` and the cursor for the next call. Cursor is opaque for
+/// clients, it is the transaction number of the previous snapshot. Parents sent by previous calls
+/// are read again, so nested targets keep the lane and depth of the first update.
+fn fetch_update(
+    postgres: &PostgreSQL,
+    connection: &Connection,
+    stage: &str,
+    cursor: Option<i64>,
+) -> HandlerResult<Update> {
+    let mut rows = Vec::new();
+    let (cursor, _) = postgres
+        .data_since(connection, stage, cursor, |target| {
+            rows.push(Row::from_target(target));
+
+            Ok::<(), Infallible>(())
+        })
+        .map_err(HandlerError::database_error)?
+        .map_err(|e| match e {})?;
+
+    let ids: HashSet<&String> = rows.iter().filter_map(|row| row.id.as_ref()).collect();
+    let missing: Vec<String> = rows
+        .iter()
+        .filter_map(|row| row.parent.as_ref())
+        .filter(|parent| !ids.contains(parent))
+        .cloned()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let mut nesting = Nesting::new();
+    let mut targets = Vec::new();
+    let mut push = |row| {
+        targets.push(row);
+
+        Ok::<(), Infallible>(())
+    };
+
+    for ancestor in &postgres
+        .ancestors(connection, stage, &missing)
+        .map_err(HandlerError::database_error)?
+    {
+        nesting.add_parent(ancestor);
+    }
+
+    for row in rows {
+        nesting
+            .push(row, &mut push)
+            .unwrap_or_else(|never| match never {});
+    }
+
+    nesting
+        .finish(&mut push)
+        .unwrap_or_else(|never| match never {});
+
+    Ok(Update { cursor, targets })
+}
+
+#[derive(Debug, Serialize)]
+struct Update {
+    cursor: i64,
+    targets: Vec<Row>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    #[serde(default)]
+    database: Option<String>,
+    stage: String,
+    #[serde(default)]
+    cursor: Option<i64>,
+    #[serde(default)]
+    interval: Option<u64>,
+    #[serde(default)]
+    channel: Option<String>,
+}
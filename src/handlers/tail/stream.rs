@@ -0,0 +1,231 @@
+use super::fetch_update;
+use super::Request;
+use crate::config::Config;
+use crate::database::Listener;
+use crate::database::PostgreSQL;
+use crate::handlers::util;
+use crate::handlers::HandlerError;
+use crate::handlers::HandlerResult;
+use iron::method::Method;
+use iron::middleware::Handler;
+use iron::mime::Mime;
+use iron::mime::SubLevel;
+use iron::mime::TopLevel;
+use iron::response::WriteBody;
+use iron::IronResult;
+use iron::Request as IronRequest;
+use iron::Response as IronResponse;
+use std::io::Result as IoResult;
+use std::io::Write;
+use std::str;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+const DEFAULT_INTERVAL: u64 = 2_000;
+const MIN_INTERVAL: u64 = 100;
+const QUERY_NUMBERS: &[&str] = &["cursor", "interval", "max_row_errors"];
+const LAST_EVENT_ID: &str = "Last-Event-ID";
+
+#[derive(Debug)]
+pub struct StreamHandler {
+    config: Arc<Config>,
+    streams: Arc<AtomicUsize>,
+}
+
+impl StreamHandler {
+    pub fn new(config: &Arc<Config>) -> StreamHandler {
+        StreamHandler {
+            config: config.clone(),
+            streams: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+impl Handler for StreamHandler {
+    /// Reads request from JSON body or from query string of `GET` request sent by `EventSource`.
+    /// Only `profile` is accepted in query string, credentials must not be part of URL. Cursor of
+    /// the last received update is taken from `Last-Event-ID` header sent on reconnection.
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        if request.method == Method::Get {
+            let last_event_id = last_event_id(request);
+
+            util::handle_query(request, QUERY_NUMBERS, |mut request: Request| {
+                request.connection.require_profile()?;
+                request.cursor = last_event_id.or(request.cursor);
+
+                self.stream(request)
+            })
+        } else {
+            util::handle_read(request, |request| self.stream(request))
+        }
+    }
+}
+
+impl StreamHandler {
+    fn stream(&self, request: Request) -> HandlerResult<(Mime, Box<dyn WriteBody>)> {
+        let server = self.config.server();
+        let slot = StreamSlot::acquire(&self.streams, server.max_streams)?;
+        let content_type = Mime(TopLevel::Text, SubLevel::Ext("event-stream".into()), vec![]);
+        let interval = request
+            .interval
+            .unwrap_or(DEFAULT_INTERVAL)
+            .max(MIN_INTERVAL);
+        let postgres = request.connection.postgres(&self.config)?;
+        let database = request
+            .connection
+            .database(&self.config, &request.database)?;
+        let stream = TailStream {
+            postgres,
+            database,
+            stage: request.stage,
+            cursor: request.cursor,
+            interval: Duration::from_millis(interval),
+            channel: request.channel,
+            lifetime: Duration::from_secs(server.stream_lifetime),
+            _slot: slot,
+        };
+        let body: Box<dyn WriteBody> = Box::new(stream);
+
+        Ok((content_type, body))
+    }
+}
+
+/// Cursor sent by `EventSource` on reconnection, invalid values are ignored.
+fn last_event_id(request: &IronRequest) -> Option<i64> {
+    request
+        .headers
+        .get_raw(LAST_EVENT_ID)
+        .and_then(|values| values.first())
+        .and_then(|value| str::from_utf8(value).ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Place in the limited number of open streams, released when the stream is dropped.
+struct StreamSlot {
+    streams: Arc<AtomicUsize>,
+}
+
+impl StreamSlot {
+    fn acquire(streams: &Arc<AtomicUsize>, limit: usize) -> HandlerResult<StreamSlot> {
+        let slot = StreamSlot {
+            streams: streams.clone(),
+        };
+
+        if streams.fetch_add(1, Ordering::SeqCst) >= limit {
+            return Err(HandlerError::too_many_streams(limit));
+        }
+
+        Ok(slot)
+    }
+}
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        self.streams.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Server-Sent Events body. Sends `update` event with the cursor as event id after every poll
+/// until client disconnects, `lifetime` expires or database error occurs, which is sent as `error`
+/// event. All polls share one connection. When `channel` is set, polls are triggered by `NOTIFY`
+/// on this channel and `interval` only limits waiting time.
+struct TailStream {
+    postgres: PostgreSQL,
+    database: String,
+    stage: String,
+    cursor: Option<i64>,
+    interval: Duration,
+    channel: Option<String>,
+    lifetime: Duration,
+    _slot: StreamSlot,
+}
+
+impl WriteBody for TailStream {
+    fn write_body(&mut self, writer: &mut dyn Write) -> IoResult<()> {
+        let started = Instant::now();
+        let connection = match self.postgres.connection(&self.database) {
+            Ok(connection) => connection,
+            Err(err) => return write_error(writer, &HandlerError::database_error(err)),
+        };
+        let listener = match &self.channel {
+            Some(channel) => match Listener::new(&connection, channel) {
+                Ok(listener) => Some(listener),
+                Err(err) => return write_error(writer, &HandlerError::database_error(err)),
+            },
+            None => None,
+        };
+
+        loop {
+            match fetch_update(&self.postgres, &connection, &self.stage, self.cursor) {
+                Ok(update) => {
+                    self.cursor = Some(update.cursor);
+
+                    write_update(writer, update.cursor, &serde_json::to_string(&update)?)?;
+                }
+                Err(err) => return write_error(writer, &err),
+            }
+
+            if started.elapsed() >= self.lifetime {
+                return Ok(());
+            }
+
+            match &listener {
+                Some(listener) => {
+                    if let Err(err) = listener.wait(self.interval) {
+                        return write_error(writer, &HandlerError::database_error(err));
+                    }
+                }
+                None => thread::sleep(self.interval),
+            }
+        }
+    }
+}
+
+/// Sends `error` event with the same JSON object as error responses of other handlers.
+fn write_error(writer: &mut dyn Write, error: &HandlerError) -> IoResult<()> {
+    write_event(writer, "error", &util::error_to_json(error))
+}
+
+/// Sends `update` event with `cursor` as id, so `EventSource` resumes from it after reconnection.
+fn write_update(writer: &mut dyn Write, cursor: i64, data: &str) -> IoResult<()> {
+    writer.write_fmt(format_args!("id: {}\n", cursor))?;
+    write_event(writer, "update", data)
+}
+
+fn write_event(writer: &mut dyn Write, event: &str, data: &str) -> IoResult<()> {
+    writer.write_fmt(format_args!("event: {}\n", event))?;
+
+    for line in data.lines() {
+        writer.write_fmt(format_args!("data: {}\n", line))?;
+    }
+
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_are_limited_and_released() {
+        let streams = Arc::new(AtomicUsize::new(0));
+        let first = StreamSlot::acquire(&streams, 2).unwrap();
+        let second = StreamSlot::acquire(&streams, 2).unwrap();
+
+        assert!(StreamSlot::acquire(&streams, 2).is_err());
+        assert_eq!(streams.load(Ordering::SeqCst), 2);
+
+        drop(first);
+
+        assert!(StreamSlot::acquire(&streams, 2).is_ok());
+
+        drop(second);
+
+        assert_eq!(streams.load(Ordering::SeqCst), 0);
+    }
+}
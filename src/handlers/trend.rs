@@ -0,0 +1,70 @@
+use super::util;
+use super::ConnectionRequest;
+use super::HandlerError;
+use crate::config::Config;
+use crate::stats;
+use iron::middleware::Handler;
+use iron::IronResult;
+use iron::Request as IronRequest;
+use iron::Response as IronResponse;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct TrendHandler {
+    config: Arc<Config>,
+}
+
+impl TrendHandler {
+    pub fn new(config: &Arc<Config>) -> TrendHandler {
+        TrendHandler {
+            config: config.clone(),
+        }
+    }
+}
+
+impl Handler for TrendHandler {
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        util::handle_request(request, move |request: Request| {
+            let stages = request.stages.unwrap_or(stats::DEFAULT_STAGES);
+
+            if !(1..=stats::MAX_STAGES).contains(&stages) {
+                return Err(HandlerError::new(&format!(
+                    "Number of stages must be between 1 and {}",
+                    stats::MAX_STAGES
+                )));
+            }
+
+            if request.name.is_none() && request.group.is_none() {
+                return Err(HandlerError::new("Either name pattern or group required"));
+            }
+
+            let postgres = request.connection.postgres(&self.config)?;
+            let database = request
+                .connection
+                .database(&self.config, &request.database)?;
+
+            stats::trend(
+                &postgres,
+                &database,
+                request.name.as_deref(),
+                request.group.as_deref(),
+                stages,
+            )
+            .map_err(HandlerError::database_error)
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    #[serde(default)]
+    database: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    stages: Option<i64>,
+}
@@ -1,52 +1,142 @@
+use super::error::ErrorCode;
+use super::HandlerError;
 use super::HandlerResult;
+use crate::database::DatabaseError;
+use crate::export::Format;
+use iron::headers::Accept;
+use iron::headers::Quality;
+use iron::headers::QualityItem;
 
 use iron::mime::Mime;
 use iron::mime::SubLevel;
 use iron::mime::TopLevel;
 use iron::modifier::Modifier;
 use iron::status;
+use iron::status::Status;
 use iron::IronResult;
 use iron::Request;
 use iron::Response;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use serde_json;
+use serde_json::Map;
+use serde_json::Value;
+use std::cmp::Reverse;
 use std::io::Read;
+use url::Url;
 
+/// Returns body of successful response, errors are sent as JSON with their code and status.
 pub fn handle_read<Req, R, F>(request: &mut Request, callback: F) -> IronResult<Response>
 where
     Req: DeserializeOwned,
     F: FnOnce(Req) -> HandlerResult<R>,
     R: Modifier<Response>,
 {
-    match serde_json::from_reader(request.body.by_ref()) {
-        Ok(request) => match callback(request) {
-            Ok(body) => Ok(Response::with((status::Ok, body))),
-            Err(err) => Ok(Response::with((status::BadRequest, format!("{}", err)))),
-        },
-        Err(err) => Ok(Response::with((status::BadRequest, format!("{}", err)))),
-    }
+    let result = serde_json::from_reader(request.body.by_ref())
+        .map_err(|err| HandlerError::new(&format!("{}", err)))
+        .and_then(callback);
+
+    body_to_response(result)
+}
+
+/// Same as `handle_read`, but request is read from query string, so it can be sent by browser
+/// `EventSource`. Parameters listed in `numbers` are parsed as integers.
+pub fn handle_query<Req, R, F>(
+    request: &mut Request,
+    numbers: &[&str],
+    callback: F,
+) -> IronResult<Response>
+where
+    Req: DeserializeOwned,
+    F: FnOnce(Req) -> HandlerResult<R>,
+    R: Modifier<Response>,
+{
+    let result = query_to_json(request, numbers)
+        .and_then(|value| {
+            serde_json::from_value(value).map_err(|err| HandlerError::new(&format!("{}", err)))
+        })
+        .and_then(callback);
+
+    body_to_response(result)
 }
 
+/// Wraps result in `{success, result}` object, errors are sent as JSON with their code and status.
 pub fn handle_request<Req, Res, F>(request: &mut Request, callback: F) -> IronResult<Response>
 where
     Req: DeserializeOwned,
     Res: Serialize,
     F: FnOnce(Req) -> HandlerResult<Res>,
 {
-    let result = match serde_json::from_reader(request.body.by_ref()) {
-        Ok(request) => callback(request),
-        Err(err) => return Ok(Response::with((status::BadRequest, format!("{}", err)))),
-    };
-    let response = match result {
-        Ok(response) => ErrorResponse::success(response),
-        Err(err) => ErrorResponse::error(&format!("{}", err)),
-    };
-
-    struct_to_response(&response)
+    let result = serde_json::from_reader(request.body.by_ref())
+        .map_err(|err| HandlerError::new(&format!("{}", err)))
+        .and_then(callback);
+
+    match result {
+        Ok(response) => struct_to_response(status::Ok, &ErrorResponse::success(response)),
+        Err(err) => error_to_response(&err),
+    }
+}
+
+/// Error serialized as `{success: false, message, code, details}` object.
+pub fn error_to_json(error: &HandlerError) -> String {
+    serde_json::to_string(&ErrorResponse::<()>::error(error))
+        .unwrap_or_else(|_| format!("{}", error))
+}
+
+fn query_to_json(request: &Request, numbers: &[&str]) -> HandlerResult<Value> {
+    let mut object = Map::new();
+    let url: &Url = request.url.as_ref();
+
+    for (name, value) in url.query_pairs() {
+        let value = if numbers.contains(&name.as_ref()) {
+            let number: i64 = value.parse().map_err(|_| {
+                HandlerError::new(&format!("Parameter `{}` must be an integer", name))
+            })?;
+
+            Value::from(number)
+        } else {
+            Value::from(value.into_owned())
+        };
+
+        object.insert(name.into_owned(), value);
+    }
+
+    Ok(Value::Object(object))
+}
+
+fn body_to_response<R>(result: HandlerResult<R>) -> IronResult<Response>
+where
+    R: Modifier<Response>,
+{
+    match result {
+        Ok(body) => Ok(Response::with((status::Ok, body))),
+        Err(err) => error_to_response(&err),
+    }
+}
+
+fn error_to_response(error: &HandlerError) -> IronResult<Response> {
+    struct_to_response(error.status(), &ErrorResponse::<()>::error(error))
+}
+
+/// Selects output format from `Accept` header, media types are tried in order of their quality.
+pub fn accept_format(request: &Request) -> Option<Format> {
+    select_format(request.headers.get::<Accept>()?)
+}
+
+/// Media types with zero quality are not acceptable, they are skipped.
+fn select_format(items: &[QualityItem<Mime>]) -> Option<Format> {
+    let mut items: Vec<_> = items
+        .iter()
+        .filter(|item| item.quality > Quality(0))
+        .collect();
+
+    items.sort_by_key(|item| Reverse(item.quality));
+    items
+        .into_iter()
+        .filter_map(|item| Format::from_mime(&item.item))
+        .next()
 }
 
-fn struct_to_response<Res>(value: &Res) -> IronResult<Response>
+fn struct_to_response<Res>(status: Status, value: &Res) -> IronResult<Response>
 where
     Res: Serialize,
 {
@@ -54,38 +144,83 @@ where
         Ok(body) => {
             let content_type = Mime(TopLevel::Application, SubLevel::Json, vec![]);
 
-            Ok(Response::with((status::Ok, content_type, body)))
+            Ok(Response::with((status, content_type, body)))
         }
         Err(_) => Ok(Response::with((status::InternalServerError,))),
     }
 }
 
 #[derive(Debug, Serialize)]
-struct ErrorResponse<T> {
+struct ErrorResponse<'a, T> {
     success: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     result: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
     message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<ErrorCode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<&'a DatabaseError>,
 }
 
-impl<T> ErrorResponse<T>
+impl<'a, T> ErrorResponse<'a, T>
 where
     T: Serialize,
 {
-    fn success(result: T) -> ErrorResponse<T> {
+    fn success(result: T) -> ErrorResponse<'a, T> {
         ErrorResponse {
             success: true,
             result: Some(result),
             message: None,
+            code: None,
+            details: None,
         }
     }
 
-    fn error(message: &str) -> ErrorResponse<T> {
+    fn error(error: &'a HandlerError) -> ErrorResponse<'a, T> {
         ErrorResponse {
             success: false,
             result: None,
-            message: Some(message.into()),
+            message: Some(format!("{}", error)),
+            code: Some(error.code()),
+            details: error.details(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iron::headers::q;
+    use iron::headers::qitem;
+
+    fn mime(value: &str) -> Mime {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn formats_ordered_by_quality() {
+        let items = [
+            QualityItem::new(mime("text/csv"), q(0.5)),
+            qitem(mime("application/x-ndjson")),
+        ];
+
+        assert_eq!(select_format(&items), Some(Format::Ndjson));
+    }
+
+    #[test]
+    fn zero_quality_is_not_acceptable() {
+        let items = [
+            QualityItem::new(mime("application/json"), q(0.0)),
+            QualityItem::new(mime("text/csv"), q(0.1)),
+        ];
+
+        assert_eq!(select_format(&items), Some(Format::Csv));
+        assert_eq!(select_format(&items[..1]), None);
+    }
+
+    #[test]
+    fn wildcards_select_nothing() {
+        assert_eq!(select_format(&[qitem(mime("*/*"))]), None);
+    }
+}
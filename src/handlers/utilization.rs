@@ -0,0 +1,65 @@
+use super::util;
+use super::ConnectionRequest;
+use super::HandlerError;
+use crate::config::Config;
+use crate::stats;
+use iron::middleware::Handler;
+use iron::IronResult;
+use iron::Request as IronRequest;
+use iron::Response as IronResponse;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct UtilizationHandler {
+    config: Arc<Config>,
+}
+
+impl UtilizationHandler {
+    pub fn new(config: &Arc<Config>) -> UtilizationHandler {
+        UtilizationHandler {
+            config: config.clone(),
+        }
+    }
+}
+
+impl Handler for UtilizationHandler {
+    fn handle(&self, request: &mut IronRequest) -> IronResult<IronResponse> {
+        util::handle_request(request, move |request: Request| {
+            let buckets = request.buckets.unwrap_or(stats::DEFAULT_BUCKETS);
+
+            if buckets == 0 || buckets > stats::MAX_BUCKETS {
+                return Err(HandlerError::new(&format!(
+                    "Number of buckets must be between 1 and {}",
+                    stats::MAX_BUCKETS
+                )));
+            }
+
+            let postgres = request.connection.postgres(&self.config)?;
+            let database = request
+                .connection
+                .database(&self.config, &request.database)?;
+
+            stats::utilization(
+                &postgres,
+                &database,
+                &request.stage,
+                buckets,
+                request.min_gap,
+            )
+            .map_err(HandlerError::database_error)
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(flatten)]
+    connection: ConnectionRequest,
+    #[serde(default)]
+    database: Option<String>,
+    stage: String,
+    #[serde(default)]
+    buckets: Option<usize>,
+    #[serde(default)]
+    min_gap: i64,
+}
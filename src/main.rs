@@ -3,18 +3,98 @@ extern crate log;
 #[macro_use]
 extern crate serde_derive;
 
+mod commands;
+mod config;
 mod database;
+mod export;
 mod handlers;
 mod options;
+mod render;
 mod server;
+mod stats;
 
+use crate::options::Command;
 use crate::options::Options;
+use env_logger::Builder;
+use env_logger::Env;
+use std::process;
 use structopt::StructOpt;
 
-fn main() {
-    env_logger::init();
+const LOG_FILTER_ENV: &str = "RUST_LOG";
+const DEFAULT_LOG_LEVEL: &str = "info";
 
+fn main() {
     let options = Options::from_args();
+    let config = match options.load_config() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+
+            process::exit(1);
+        }
+    };
+
+    if options.print_config() {
+        match config.to_toml() {
+            Ok(content) => print!("{}", content),
+            Err(err) => {
+                eprintln!("{}", err);
+
+                process::exit(1);
+            }
+        }
+
+        return;
+    }
+
+    let mut logger = match &config.logging().level {
+        Some(level) => {
+            let mut logger = Builder::new();
+
+            logger.parse(level);
+            logger
+        }
+        None => Builder::from_env(Env::default().filter_or(LOG_FILTER_ENV, DEFAULT_LOG_LEVEL)),
+    };
+
+    logger.init();
+
+    let result = match options.command() {
+        Some(Command::Export(command)) => commands::export(&config, command),
+        Some(Command::Render(command)) => commands::render(&config, command),
+        Some(Command::Stats(command)) => commands::stats(&config, command),
+        Some(Command::Utilization(command)) => commands::utilization(&config, command),
+        Some(Command::Compare(command)) => commands::compare(&config, command),
+        Some(Command::CriticalPath(command)) => commands::critical_path(&config, command),
+        Some(Command::InferredPath(command)) => commands::inferred_path(&config, command),
+        Some(Command::Simulate(command)) => commands::simulate(&config, command),
+        Some(Command::Quality(command)) => commands::quality(&config, command),
+        Some(Command::Serve) | None => {
+            if let Err(err) = config.validate_server() {
+                eprintln!("{}", err);
+
+                process::exit(1);
+            }
+
+            // PNG labels need the font, it is loaded once, so a missing font is found at startup.
+            let font = match render::load_font(&config.render().font) {
+                Ok(font) => font,
+                Err(err) => {
+                    eprintln!("{}", err);
+
+                    process::exit(1);
+                }
+            };
+
+            server::start(config, font);
+
+            Ok(())
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
 
-    server::start(&options);
+        process::exit(err.exit_code());
+    }
 }
@@ -1,4 +1,13 @@
-use std::path::Path;
+use crate::config::Config;
+use crate::config::ConfigResult;
+use crate::database::IntervalPolicy;
+use crate::export::Format;
+use crate::export::TraceProcess;
+use crate::render::ImageFormat;
+use crate::render::Theme;
+use crate::stats::InferenceScope;
+use crate::stats::SchedulingPolicy;
+use crate::stats::MAX_WORKERS;
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -9,41 +18,417 @@ pub struct Options {
         short = "a",
         long = "address",
         name = "ADDR",
-        help = "Listen on given address",
-        default_value = "localhost"
+        env = "GANTT_ADDRESS",
+        help = "Listen on given address [default: localhost]"
     )]
-    address: String,
+    address: Option<String>,
 
     #[structopt(
         short = "p",
         long = "port",
         name = "PORT",
-        help = "Listen on given port",
-        default_value = "8080"
+        env = "GANTT_PORT",
+        help = "Listen on given port [default: 8080]"
     )]
-    port: u16,
+    port: Option<u16>,
 
     #[structopt(
         short = "t",
         long = "temp-dir",
         name = "PATH",
-        help = "Path to temporary directory",
-        default_value = ".",
+        env = "GANTT_TEMP_DIR",
+        help = "Path to temporary directory [default: .]",
         parse(from_os_str)
     )]
-    temp_dir: PathBuf,
+    temp_dir: Option<PathBuf>,
+
+    #[structopt(
+        short = "s",
+        long = "static-dir",
+        name = "STATIC",
+        env = "GANTT_STATIC_DIR",
+        help = "Path to directory with web interface files [default: public]",
+        parse(from_os_str)
+    )]
+    static_dir: Option<PathBuf>,
+
+    #[structopt(
+        short = "l",
+        long = "log-level",
+        name = "LEVEL",
+        env = "GANTT_LOG_LEVEL",
+        help = "Log filter in env_logger format [default: RUST_LOG or info]"
+    )]
+    log_level: Option<String>,
+
+    #[structopt(
+        short = "c",
+        long = "config",
+        name = "CONFIG",
+        env = "GANTT_CONFIG",
+        help = "Path to configuration file",
+        parse(from_os_str)
+    )]
+    config: Option<PathBuf>,
+
+    #[structopt(
+        long = "allow-credentials",
+        help = "Allow clients to connect with server name, user and password instead of profile"
+    )]
+    allow_credentials: bool,
+
+    #[structopt(long = "print-config", help = "Print effective configuration and exit")]
+    print_config: bool,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    #[structopt(name = "serve", about = "Start web server, used when no command given")]
+    Serve,
+
+    #[structopt(name = "export", about = "Write stage targets in one of data formats")]
+    Export(ExportCommand),
+
+    #[structopt(name = "render", about = "Render stage chart to PNG or SVG image")]
+    Render(RenderCommand),
+
+    #[structopt(name = "stats", about = "Print stage statistics in JSON format")]
+    Stats(StatsCommand),
+
+    #[structopt(
+        name = "utilization",
+        about = "Print thread utilization, idle gaps and concurrency in JSON format"
+    )]
+    Utilization(UtilizationCommand),
+
+    #[structopt(
+        name = "compare",
+        about = "Print target duration differences between two stages in JSON format"
+    )]
+    Compare(CompareCommand),
+
+    #[structopt(
+        name = "critical-path",
+        about = "Print critical path and slack of every target in JSON format"
+    )]
+    CriticalPath(CriticalPathCommand),
+
+    #[structopt(
+        name = "inferred-path",
+        about = "Print chain of targets explaining stage duration, inferred from timing, in JSON \
+                 format"
+    )]
+    InferredPath(InferredPathCommand),
+
+    #[structopt(
+        name = "simulate",
+        about = "Replay stage targets on given number of simulated threads"
+    )]
+    Simulate(SimulateCommand),
+
+    #[structopt(
+        name = "quality",
+        about = "Print data quality report of the stage in JSON format"
+    )]
+    Quality(QualityCommand),
+}
+
+/// Stage selection shared by offline commands.
+#[derive(StructOpt, Debug)]
+pub struct StageArgs {
+    #[structopt(long = "profile", name = "PROFILE", help = "Connection profile name")]
+    pub profile: String,
+
+    #[structopt(
+        long = "database",
+        name = "DATABASE",
+        help = "Database name [default: database of the profile]"
+    )]
+    pub database: Option<String>,
+
+    #[structopt(long = "stage", name = "STAGE", help = "Stage name")]
+    pub stage: String,
+
+    #[structopt(
+        short = "o",
+        long = "output",
+        name = "FILE",
+        help = "Output file [default: stdout]",
+        parse(from_os_str)
+    )]
+    pub output: Option<PathBuf>,
+
+    #[structopt(
+        long = "invalid-intervals",
+        name = "INTERVAL_POLICY",
+        help = "Targets ending before start: clamp, drop, swap, milestone or fail \
+                [default: policy of the profile mapping]"
+    )]
+    pub invalid_intervals: Option<IntervalPolicy>,
+
+    #[structopt(
+        long = "max-row-errors",
+        name = "MAX_ROW_ERRORS",
+        help = "Number of rows which can not be read skipped before command fails \
+                [default: limit of the profile mapping]"
+    )]
+    pub max_row_errors: Option<usize>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ExportCommand {
+    #[structopt(flatten)]
+    pub stage: StageArgs,
+
+    #[structopt(
+        long = "format",
+        name = "FORMAT",
+        default_value = "csv",
+        help = "Output format: dlm, csv, json, ndjson or trace"
+    )]
+    pub format: Format,
+
+    #[structopt(
+        long = "trace-process",
+        name = "PROCESS",
+        default_value = "stage",
+        help = "Trace process grouping: stage or group"
+    )]
+    pub trace_process: TraceProcess,
+
+    #[structopt(
+        long = "critical-path",
+        help = "Set critical flag of targets on critical path, requires mapped dependencies"
+    )]
+    pub critical_path: bool,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct RenderCommand {
+    #[structopt(flatten)]
+    pub stage: StageArgs,
+
+    #[structopt(
+        long = "format",
+        name = "FORMAT",
+        help = "Image format, png or svg [default: svg if output ends with .svg, otherwise png]"
+    )]
+    pub format: Option<ImageFormat>,
+
+    #[structopt(long = "width", name = "WIDTH", help = "Image width [default: 1200]")]
+    pub width: Option<u32>,
+
+    #[structopt(
+        long = "height",
+        name = "HEIGHT",
+        help = "Image height [default: 32 pixels per thread]"
+    )]
+    pub height: Option<u32>,
+
+    #[structopt(long = "dpi", name = "DPI", help = "Image resolution [default: 96]")]
+    pub dpi: Option<u32>,
+
+    #[structopt(
+        long = "theme",
+        name = "THEME",
+        help = "Color theme, light or dark [default: light]"
+    )]
+    pub theme: Option<Theme>,
+
+    #[structopt(
+        long = "rename-group",
+        name = "FROM=TO",
+        help = "Rename group before colors assigned, may be repeated",
+        parse(try_from_str = "parse_rename")
+    )]
+    pub rename_group: Vec<(String, String)>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct StatsCommand {
+    #[structopt(flatten)]
+    pub stage: StageArgs,
+
+    #[structopt(
+        long = "longest",
+        name = "COUNT",
+        default_value = "10",
+        help = "Number of longest targets to show"
+    )]
+    pub longest: usize,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct UtilizationCommand {
+    #[structopt(flatten)]
+    pub stage: StageArgs,
+
+    #[structopt(
+        long = "buckets",
+        name = "BUCKETS",
+        default_value = "100",
+        help = "Number of concurrency timeline buckets"
+    )]
+    pub buckets: usize,
+
+    #[structopt(
+        long = "min-gap",
+        name = "MILLIS",
+        default_value = "0",
+        help = "Do not list idle gaps shorter than given number of milliseconds"
+    )]
+    pub min_gap: i64,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CompareCommand {
+    #[structopt(flatten)]
+    pub stage: StageArgs,
+
+    #[structopt(
+        long = "with-stage",
+        name = "WITH_STAGE",
+        help = "Stage compared with base stage"
+    )]
+    pub with_stage: String,
+
+    #[structopt(
+        long = "with-database",
+        name = "WITH_DATABASE",
+        help = "Database of compared stage [default: database of base stage]"
+    )]
+    pub with_database: Option<String>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct QualityCommand {
+    #[structopt(flatten)]
+    pub stage: StageArgs,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CriticalPathCommand {
+    #[structopt(flatten)]
+    pub stage: StageArgs,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct InferredPathCommand {
+    #[structopt(flatten)]
+    pub stage: StageArgs,
+
+    #[structopt(
+        long = "tolerance",
+        name = "MILLIS",
+        default_value = "1000",
+        help = "Maximum time between the end of predecessor and the start of target"
+    )]
+    pub tolerance: u32,
+
+    #[structopt(
+        long = "scope",
+        name = "SCOPE",
+        default_value = "any",
+        help = "Threads of predecessors: any or thread (same thread only)"
+    )]
+    pub scope: InferenceScope,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct SimulateCommand {
+    #[structopt(flatten)]
+    pub stage: StageArgs,
+
+    #[structopt(
+        long = "workers",
+        name = "WORKERS",
+        help = "Number of simulated threads",
+        parse(try_from_str = "parse_workers")
+    )]
+    pub workers: usize,
+
+    #[structopt(
+        long = "policy",
+        name = "POLICY",
+        default_value = "original",
+        help = "Order of ready targets: original (start time) or longest (duration)"
+    )]
+    pub policy: SchedulingPolicy,
+
+    #[structopt(
+        long = "format",
+        name = "FORMAT",
+        help = "Write simulated timeline in data format: dlm, csv, json, ndjson or trace \
+                [default: summary in JSON format]"
+    )]
+    pub format: Option<Format>,
+}
+
+fn parse_rename(value: &str) -> Result<(String, String), String> {
+    match value.find('=') {
+        Some(index) => Ok((value[..index].into(), value[index + 1..].into())),
+        None => Err(format!("Expected FROM=TO, got `{}`", value)),
+    }
 }
 
 impl Options {
-    pub fn address(&self) -> &str {
-        &self.address
+    pub fn print_config(&self) -> bool {
+        self.print_config
     }
 
-    pub fn port(&self) -> u16 {
-        self.port
+    pub fn command(&self) -> Option<&Command> {
+        self.command.as_ref()
     }
 
-    pub fn temp_dir(&self) -> &Path {
-        &self.temp_dir
+    /// Reads configuration file if given and overrides its values with command line arguments and
+    /// environment variables.
+    pub fn load_config(&self) -> ConfigResult<Config> {
+        let mut config = match &self.config {
+            Some(path) => Config::load(path)?,
+            None => Config::default(),
+        };
+
+        if self.allow_credentials {
+            config.set_allow_credentials(true);
+        }
+
+        let server = config.server_mut();
+
+        if let Some(address) = &self.address {
+            server.address = address.clone();
+        }
+
+        if let Some(port) = self.port {
+            server.port = port;
+        }
+
+        if let Some(temp_dir) = &self.temp_dir {
+            server.temp_dir = temp_dir.clone();
+        }
+
+        if let Some(static_dir) = &self.static_dir {
+            server.static_dir = static_dir.clone();
+        }
+
+        if let Some(log_level) = &self.log_level {
+            config.logging_mut().level = Some(log_level.clone());
+        }
+
+        config.validate()?;
+
+        Ok(config)
+    }
+}
+
+fn parse_workers(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(workers) if (1..=MAX_WORKERS).contains(&workers) => Ok(workers),
+        _ => Err(format!(
+            "Number of workers must be between 1 and {}, got `{}`",
+            MAX_WORKERS, value
+        )),
     }
 }
@@ -0,0 +1,131 @@
+use crate::export;
+
+const SECOND: i64 = 1_000;
+const MINUTE: i64 = 60 * SECOND;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+const YEAR: i64 = 365 * DAY;
+
+const TICK_INTERVALS: [i64; 24] = [
+    1,
+    2,
+    5,
+    10,
+    20,
+    50,
+    100,
+    200,
+    500,
+    SECOND,
+    5 * SECOND,
+    15 * SECOND,
+    30 * SECOND,
+    MINUTE,
+    5 * MINUTE,
+    15 * MINUTE,
+    30 * MINUTE,
+    HOUR,
+    3 * HOUR,
+    6 * HOUR,
+    12 * HOUR,
+    DAY,
+    2 * DAY,
+    7 * DAY,
+];
+
+const MONTH_INTERVALS: [(i64, i32); 3] = [(30 * DAY, 1), (90 * DAY, 3), (YEAR, 12)];
+
+const DATE_FORMAT: &str = "%Y.%m.%d";
+const MINUTES_FORMAT: &str = "%H:%M";
+const SECONDS_FORMAT: &str = "%H:%M:%S";
+
+/// Returns round time values between `min` and `max` (milliseconds since epoch), at most about
+/// `count` values. Intervals are the same as used by `d3.scaleUtc().ticks()`.
+pub fn ticks(min: i64, max: i64, count: i64) -> Vec<i64> {
+    let target = (max - min) / count.max(1);
+
+    if let Some(&interval) = TICK_INTERVALS.iter().find(|&&interval| interval >= target) {
+        return fixed_ticks(min, max, interval);
+    }
+
+    match MONTH_INTERVALS
+        .iter()
+        .find(|&&(interval, _)| interval >= target)
+    {
+        Some(&(_, months)) => month_ticks(min, max, months),
+        None => month_ticks(min, max, 12 * ((target + YEAR - 1) / YEAR) as i32),
+    }
+}
+
+fn fixed_ticks(min: i64, max: i64, interval: i64) -> Vec<i64> {
+    let mut tick = min.div_euclid(interval) * interval;
+    let mut result = Vec::new();
+
+    if tick < min {
+        tick += interval;
+    }
+
+    while tick <= max {
+        result.push(tick);
+        tick += interval;
+    }
+
+    result
+}
+
+/// Ticks at the first day of every `months` month (UTC), months are counted from year start.
+fn month_ticks(min: i64, max: i64, months: i32) -> Vec<i64> {
+    let start = time::at_utc(export::from_millis(min));
+    let mut month = (start.tm_year * 12 + start.tm_mon).div_euclid(months) * months;
+    let mut result = Vec::new();
+
+    loop {
+        let tick = export::to_millis(
+            time::Tm {
+                tm_year: month.div_euclid(12),
+                tm_mon: month.rem_euclid(12),
+                tm_mday: 1,
+                ..time::empty_tm()
+            }
+            .to_timespec(),
+        );
+
+        if tick > max {
+            break;
+        }
+
+        if tick >= min {
+            result.push(tick);
+        }
+
+        month += months;
+    }
+
+    result
+}
+
+/// Formats tick as date and time labels. Precision of time depends on visible time span, like in
+/// the web interface. Labels always use UTC.
+pub fn tick_labels(tick: i64, span: i64) -> (String, String) {
+    let time = time::at_utc(export::from_millis(tick));
+    let date = format_time(&time, DATE_FORMAT);
+    let time = if span > 10 * HOUR {
+        format_time(&time, MINUTES_FORMAT)
+    } else if span > 30 * SECOND {
+        format_time(&time, SECONDS_FORMAT)
+    } else {
+        format!(
+            "{}.{:03}",
+            format_time(&time, SECONDS_FORMAT),
+            tick.rem_euclid(SECOND)
+        )
+    };
+
+    (date, time)
+}
+
+fn format_time(time: &time::Tm, format: &str) -> String {
+    time.strftime(format)
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,131 @@
+/// RGBA color, alpha 255 is fully opaque.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+impl Color {
+    pub const fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Color {
+        Color {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// Converts HSL color to RGB the same way as `d3.hsl(...).rgb()` does, so group colors match
+    /// colors in the web interface.
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Color {
+        let hue = hue.rem_euclid(360.0);
+        let m2 = lightness
+            + if lightness < 0.5 {
+                lightness
+            } else {
+                1.0 - lightness
+            } * saturation;
+        let m1 = 2.0 * lightness - m2;
+        let red = hsl_channel(
+            if hue >= 240.0 {
+                hue - 240.0
+            } else {
+                hue + 120.0
+            },
+            m1,
+            m2,
+        );
+        let green = hsl_channel(hue, m1, m2);
+        let blue = hsl_channel(
+            if hue < 120.0 {
+                hue + 240.0
+            } else {
+                hue - 120.0
+            },
+            m1,
+            m2,
+        );
+
+        Color::rgba(red, green, blue, 255)
+    }
+
+    pub fn with_alpha(self, alpha: f64) -> Color {
+        Color {
+            alpha: (f64::from(self.alpha) * alpha).round() as u8,
+            ..self
+        }
+    }
+
+    pub fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+
+    pub fn opacity(self) -> f64 {
+        f64::from(self.alpha) / 255.0
+    }
+}
+
+fn hsl_channel(hue: f64, m1: f64, m2: f64) -> u8 {
+    let value = if hue < 60.0 {
+        m1 + (m2 - m1) * hue / 60.0
+    } else if hue < 180.0 {
+        m2
+    } else if hue < 240.0 {
+        m1 + (m2 - m1) * (240.0 - hue) / 60.0
+    } else {
+        m1
+    };
+
+    (value * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stroke {
+    pub color: Color,
+    pub width: f64,
+    pub dash: Option<(f64, f64)>,
+}
+
+impl Stroke {
+    pub const fn solid(color: Color, width: f64) -> Stroke {
+        Stroke {
+            color,
+            width,
+            dash: None,
+        }
+    }
+
+    pub const fn dashed(color: Color, width: f64, dash: (f64, f64)) -> Stroke {
+        Stroke {
+            color,
+            width,
+            dash: Some(dash),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// Drawing surface used by the chart. Coordinates are pixels, text is drawn using single font and
+/// `y` is the text baseline.
+pub trait Canvas {
+    fn fill(&mut self, color: Color);
+
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), stroke: Stroke);
+
+    fn rect(&mut self, origin: (f64, f64), size: (f64, f64), fill: Color, stroke: Stroke);
+
+    /// Draws closed polygon, does nothing when there are less than three points.
+    fn polygon(&mut self, points: &[(f64, f64)], fill: Color, stroke: Stroke);
+
+    fn text(&mut self, position: (f64, f64), text: &str, anchor: Anchor, color: Color);
+
+    fn text_width(&self, text: &str) -> f64;
+}
@@ -0,0 +1,559 @@
+use super::axis;
+use super::canvas::Anchor;
+use super::canvas::Canvas;
+use super::canvas::Color;
+use super::canvas::Stroke;
+use super::theme::Palette;
+use super::RenderOptions;
+use super::RenderResult;
+use crate::export::Row;
+use crate::export::RowKind;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+const THRESHOLD_DRAW: f64 = 0.01;
+const THRESHOLD_TEXT: f64 = 100.0;
+const HELLIP: &str = "\u{2026}";
+
+const CHART_PADDING_LEFT: f64 = 140.0;
+const CHART_PADDING_RIGHT: f64 = 60.0;
+const CHART_PADDING_TOP: f64 = 1.0;
+const CHART_PADDING_BOTTOM: f64 = 35.0;
+const CHART_THREAD_HEIGHT: f64 = 32.0;
+const CHART_THREAD_OFFSET: f64 = 6.5;
+const CHART_BLOCK_HEIGHT: f64 = 21.0;
+const CHART_NESTED_INSET: f64 = 3.0;
+const CHART_NESTED_MIN_HEIGHT: f64 = 3.0;
+const CHART_RUNNING_ALPHA: f64 = 0.5;
+const CHART_RUNNING_DASH: (f64, f64) = (4.0, 3.0);
+const CHART_MARKER_DASH: (f64, f64) = (6.0, 4.0);
+const CHART_MARKER_TEXT_OFFSET_X: f64 = 4.0;
+const CHART_MARKER_TEXT_OFFSET_Y: f64 = 14.0;
+const CHART_TEXT_OFFSET_X: f64 = 8.0;
+const CHART_TEXT_PADDING_LEFT: f64 = 8.0;
+const CHART_TEXT_OFFSET_Y: f64 = 23.0;
+const CHART_TEXT_MIN_HEIGHT: f64 = 16.0;
+const CHART_TICKS: i64 = 10;
+
+const X_AXIS_TICK_SIZE: f64 = 5.0;
+const X_AXIS_DATE_LABEL_OFFSET: f64 = 20.0;
+const X_AXIS_TIME_LABEL_OFFSET: f64 = 35.0;
+
+const Y_AXIS_TICK_SIZE: f64 = 5.0;
+const Y_AXIS_LABEL_SHIFT: f64 = 3.0;
+const Y_AXIS_LABEL_OFFSET: f64 = 21.0;
+
+/// Gantt chart laid out the same way as the chart in the web interface: one lane per thread
+/// sorted by name and one color per (renamed) group. Nested targets are drawn inside their parent
+/// block, every level is inset a bit more. Milestones are diamonds on their lane, markers are
+/// dashed vertical lines across all lanes.
+#[derive(Debug)]
+pub struct Chart {
+    width: f64,
+    height: f64,
+    lane_height: f64,
+    lanes: Vec<String>,
+    blocks: Vec<Block>,
+    markers: Vec<Marker>,
+    palette: Palette,
+    view_min: i64,
+    view_max: i64,
+}
+
+#[derive(Debug)]
+struct Block {
+    name: String,
+    start: i64,
+    end: i64,
+    lane: usize,
+    color: Color,
+    running: bool,
+    depth: usize,
+    milestone: bool,
+}
+
+#[derive(Debug)]
+struct Marker {
+    name: String,
+    time: i64,
+}
+
+impl Chart {
+    pub fn new(rows: &[Row], options: &RenderOptions) -> RenderResult<Chart> {
+        options.validate()?;
+
+        let group_name = |row: &Row| {
+            options
+                .group_mapping
+                .get(&row.group)
+                .cloned()
+                .unwrap_or_else(|| row.group.clone())
+        };
+        let (markers, rows): (Vec<&Row>, Vec<&Row>) =
+            rows.iter().partition(|row| row.kind == RowKind::Marker);
+        let threads: BTreeSet<&str> = rows.iter().map(|row| row.thread.as_str()).collect();
+        let groups: BTreeSet<String> = rows.iter().map(|&row| group_name(row)).collect();
+        let lane_indexes: BTreeMap<&str, usize> = threads
+            .iter()
+            .enumerate()
+            .map(|(index, &name)| (name, index))
+            .collect();
+        let group_colors: BTreeMap<&str, Color> = groups
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let hue = 360.0 * index as f64 / groups.len() as f64;
+
+                (name.as_str(), Color::from_hsl(hue, 1.0, 0.7))
+            })
+            .collect();
+        let blocks = rows
+            .iter()
+            .map(|&row| Block {
+                name: row.name.clone(),
+                start: row.start,
+                end: row.end,
+                lane: lane_indexes[row.thread.as_str()],
+                color: group_colors[group_name(row).as_str()],
+                running: row.running,
+                depth: row.depth,
+                milestone: row.kind == RowKind::Milestone,
+            })
+            .collect();
+        let markers: Vec<Marker> = markers
+            .iter()
+            .map(|row| Marker {
+                name: row.name.clone(),
+                time: row.start,
+            })
+            .collect();
+        let times = rows
+            .iter()
+            .map(|row| (row.start, row.end))
+            .chain(markers.iter().map(|marker| (marker.time, marker.time)));
+        let time_min = times.clone().map(|(start, _)| start).min().unwrap_or(0);
+        let time_max = times.map(|(_, end)| end).max().unwrap_or(time_min);
+        let view_min = options.start.unwrap_or(time_min);
+        let view_max = options.end.unwrap_or(time_max).max(view_min + 1);
+        let n_lanes = threads.len();
+        let (height, lane_height) = match options.height {
+            Some(height) if n_lanes > 0 => {
+                let height = f64::from(height);
+                let lanes_height = height - CHART_PADDING_TOP - CHART_PADDING_BOTTOM;
+
+                (height, lanes_height.max(0.0) / n_lanes as f64)
+            }
+            Some(height) => (f64::from(height), CHART_THREAD_HEIGHT),
+            None => (
+                CHART_PADDING_TOP + CHART_PADDING_BOTTOM + CHART_THREAD_HEIGHT * n_lanes as f64,
+                CHART_THREAD_HEIGHT,
+            ),
+        };
+
+        Ok(Chart {
+            width: f64::from(options.width),
+            height,
+            lane_height,
+            lanes: threads.into_iter().map(String::from).collect(),
+            blocks,
+            markers,
+            palette: options.theme.palette(),
+            view_min,
+            view_max,
+        })
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    pub fn draw(&self, canvas: &mut dyn Canvas) {
+        canvas.fill(self.palette.background);
+
+        if self.lanes.is_empty() {
+            return;
+        }
+
+        self.draw_y_axis(canvas);
+        self.draw_x_axis(canvas);
+        self.draw_blocks(canvas);
+        self.draw_markers(canvas);
+    }
+
+    /// Scales lane relative distances, chart in the web interface always uses 32 pixel lanes.
+    fn lane_scale(&self, value: f64) -> f64 {
+        value * self.lane_height / CHART_THREAD_HEIGHT
+    }
+
+    fn plot_left(&self) -> f64 {
+        CHART_PADDING_LEFT
+    }
+
+    fn plot_right(&self) -> f64 {
+        self.width - CHART_PADDING_RIGHT
+    }
+
+    fn plot_bottom(&self) -> f64 {
+        CHART_PADDING_TOP + self.lane_height * self.lanes.len() as f64
+    }
+
+    fn x(&self, time: i64) -> f64 {
+        let offset = (time - self.view_min) as f64 / (self.view_max - self.view_min) as f64;
+
+        self.plot_left() + offset * (self.plot_right() - self.plot_left())
+    }
+
+    fn draw_y_axis(&self, canvas: &mut dyn Canvas) {
+        for (index, name) in self.lanes.iter().enumerate() {
+            let y = CHART_PADDING_TOP + self.lane_height * index as f64;
+
+            canvas.line(
+                (self.plot_left(), y),
+                (self.plot_right(), y),
+                Stroke::solid(self.palette.secondary, 2.0),
+            );
+            canvas.line(
+                (self.plot_left() - Y_AXIS_TICK_SIZE, y),
+                (self.plot_left(), y),
+                Stroke::solid(self.palette.primary, 2.0),
+            );
+            canvas.text(
+                (
+                    self.plot_left() - Y_AXIS_LABEL_SHIFT,
+                    y + self.lane_scale(Y_AXIS_LABEL_OFFSET),
+                ),
+                name,
+                Anchor::End,
+                self.palette.label,
+            );
+        }
+
+        canvas.line(
+            (self.plot_left(), CHART_PADDING_TOP),
+            (self.plot_left(), self.plot_bottom()),
+            Stroke::solid(self.palette.primary, 2.0),
+        );
+    }
+
+    fn draw_x_axis(&self, canvas: &mut dyn Canvas) {
+        let span = self.view_max - self.view_min;
+        let bottom = self.plot_bottom();
+        let labels_top = bottom - CHART_PADDING_TOP;
+
+        for tick in axis::ticks(self.view_min, self.view_max, CHART_TICKS) {
+            let x = self.x(tick);
+            let (date_label, time_label) = axis::tick_labels(tick, span);
+
+            canvas.line(
+                (x, CHART_PADDING_TOP),
+                (x, bottom),
+                Stroke::solid(self.palette.secondary, 2.0),
+            );
+            canvas.line(
+                (x, bottom),
+                (x, bottom + X_AXIS_TICK_SIZE),
+                Stroke::solid(self.palette.primary, 2.0),
+            );
+            canvas.text(
+                (x, labels_top + X_AXIS_DATE_LABEL_OFFSET),
+                &date_label,
+                Anchor::Middle,
+                self.palette.label,
+            );
+            canvas.text(
+                (x, labels_top + X_AXIS_TIME_LABEL_OFFSET),
+                &time_label,
+                Anchor::Middle,
+                self.palette.label,
+            );
+        }
+
+        canvas.line(
+            (self.plot_left(), bottom),
+            (self.plot_right(), bottom),
+            Stroke::solid(self.palette.primary, 2.0),
+        );
+    }
+
+    /// Draws blocks first, milestones over them and labels of top-level blocks last, so nested
+    /// blocks do not cover labels of their parents.
+    fn draw_blocks(&self, canvas: &mut dyn Canvas) {
+        let text_visible = self.lane_scale(CHART_BLOCK_HEIGHT) >= CHART_TEXT_MIN_HEIGHT;
+
+        for block in self.blocks.iter().filter(|block| !block.milestone) {
+            let (start_x, width, lane_y) = match self.block_position(block) {
+                Some(position) => position,
+                None => continue,
+            };
+            let (fill, stroke) = if block.running {
+                (
+                    block.color.with_alpha(CHART_RUNNING_ALPHA),
+                    Stroke::dashed(self.palette.block_stroke, 1.0, CHART_RUNNING_DASH),
+                )
+            } else {
+                (block.color, Stroke::solid(self.palette.block_stroke, 1.0))
+            };
+            let inset = (CHART_NESTED_INSET * block.depth as f64)
+                .min((CHART_BLOCK_HEIGHT - CHART_NESTED_MIN_HEIGHT) / 2.0);
+
+            canvas.rect(
+                (
+                    start_x,
+                    lane_y + self.lane_scale(CHART_THREAD_OFFSET + inset),
+                ),
+                (width, self.lane_scale(CHART_BLOCK_HEIGHT - 2.0 * inset)),
+                fill,
+                stroke,
+            );
+        }
+
+        for block in self.blocks.iter().filter(|block| block.milestone) {
+            self.draw_milestone(canvas, block);
+        }
+
+        if !text_visible {
+            return;
+        }
+
+        for block in self
+            .blocks
+            .iter()
+            .filter(|block| block.depth == 0 && !block.milestone)
+        {
+            let (start_x, width, lane_y) = match self.block_position(block) {
+                Some(position) => position,
+                None => continue,
+            };
+
+            if width > THRESHOLD_TEXT {
+                let block_width = width - CHART_TEXT_OFFSET_X - CHART_TEXT_PADDING_LEFT;
+
+                canvas.text(
+                    (
+                        start_x + CHART_TEXT_OFFSET_X,
+                        lane_y + self.lane_scale(CHART_TEXT_OFFSET_Y),
+                    ),
+                    &fit_text(canvas, &block.name, block_width),
+                    Anchor::Start,
+                    self.palette.block_label,
+                );
+            }
+        }
+    }
+
+    /// Draws milestone as diamond centered at its time, diamond height equals block height.
+    fn draw_milestone(&self, canvas: &mut dyn Canvas, block: &Block) {
+        if block.start < self.view_min || block.start > self.view_max {
+            return;
+        }
+
+        let x = self.x(block.start);
+        let lane_y = CHART_PADDING_TOP + self.lane_height * block.lane as f64;
+        let half = self.lane_scale(CHART_BLOCK_HEIGHT) / 2.0;
+        let y = lane_y + self.lane_scale(CHART_THREAD_OFFSET) + half;
+
+        canvas.polygon(
+            &[(x, y - half), (x + half, y), (x, y + half), (x - half, y)],
+            block.color,
+            Stroke::solid(self.palette.block_stroke, 1.0),
+        );
+    }
+
+    /// Draws markers over all lanes with their names at the top of the chart.
+    fn draw_markers(&self, canvas: &mut dyn Canvas) {
+        for marker in &self.markers {
+            if marker.time < self.view_min || marker.time > self.view_max {
+                continue;
+            }
+
+            let x = self.x(marker.time);
+
+            canvas.line(
+                (x, CHART_PADDING_TOP),
+                (x, self.plot_bottom()),
+                Stroke::dashed(self.palette.primary, 1.0, CHART_MARKER_DASH),
+            );
+            canvas.text(
+                (
+                    x + CHART_MARKER_TEXT_OFFSET_X,
+                    CHART_PADDING_TOP + CHART_MARKER_TEXT_OFFSET_Y,
+                ),
+                &marker.name,
+                Anchor::Start,
+                self.palette.label,
+            );
+        }
+    }
+
+    /// Returns left side, width and lane top of the visible part of the block.
+    fn block_position(&self, block: &Block) -> Option<(f64, f64, f64)> {
+        if block.end < self.view_min || block.start > self.view_max {
+            return None;
+        }
+
+        let start_x = self.x(block.start).max(self.plot_left());
+        let end_x = self.x(block.end).min(self.plot_right());
+        let width = end_x - start_x;
+
+        if width < THRESHOLD_DRAW {
+            return None;
+        }
+
+        Some((
+            start_x,
+            width,
+            CHART_PADDING_TOP + self.lane_height * block.lane as f64,
+        ))
+    }
+}
+
+/// Shortens text with ellipsis until it fits into given width.
+fn fit_text(canvas: &dyn Canvas, text: &str, width: f64) -> String {
+    let text_width = canvas.text_width(text);
+
+    if text_width < width {
+        return text.into();
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut trim_index = ((width / text_width) * chars.len() as f64).ceil() as usize;
+    let mut result;
+
+    trim_index = trim_index.min(chars.len());
+
+    loop {
+        result = chars[..trim_index].iter().collect::<String>() + HELLIP;
+
+        if trim_index == 0 || canvas.text_width(&result) <= width {
+            break;
+        }
+
+        trim_index -= 1;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> RenderOptions {
+        RenderOptions {
+            width: 1_200,
+            ..RenderOptions::default()
+        }
+    }
+
+    #[test]
+    fn lanes_and_colors_follow_sorted_names() {
+        let rows = [
+            Row::task("a", 0, 10).group("test").thread("worker-2"),
+            Row::task("b", 0, 10).group("compile").thread("worker-1"),
+            Row::task("c", 10, 20).group("link").thread("worker-2"),
+            Row::task("d", 10, 20).group("lint").thread("worker-3"),
+        ];
+        let mut options = options();
+
+        options
+            .group_mapping
+            .insert("lint".into(), "compile".into());
+
+        let chart = Chart::new(&rows, &options).unwrap();
+        let lanes: Vec<usize> = chart.blocks.iter().map(|block| block.lane).collect();
+        let colors: Vec<String> = chart.blocks.iter().map(|block| block.color.hex()).collect();
+
+        assert_eq!(chart.lanes, vec!["worker-1", "worker-2", "worker-3"]);
+        assert_eq!(lanes, vec![1, 0, 1, 2]);
+        // d3.hsl(360 * index / 3, 1.0, 0.7) of sorted groups compile, link and test.
+        assert_eq!(colors, vec!["#6666ff", "#ff6666", "#66ff66", "#ff6666"]);
+    }
+
+    #[test]
+    fn blocks_are_clipped_to_time_window() {
+        let rows = [
+            Row::task("before", 0, 50).thread("t"),
+            Row::task("across", 50, 250).thread("t"),
+            Row::task("inside", 120, 180).thread("t"),
+            Row::task("after", 250, 300).thread("t"),
+        ];
+        let chart = Chart::new(
+            &rows,
+            &RenderOptions {
+                start: Some(100),
+                end: Some(200),
+                ..options()
+            },
+        )
+        .unwrap();
+        let positions: Vec<Option<(f64, f64)>> = chart
+            .blocks
+            .iter()
+            .map(|block| {
+                chart
+                    .block_position(block)
+                    .map(|(start_x, width, _)| (start_x, width))
+            })
+            .collect();
+        let plot_width = 1_200.0 - CHART_PADDING_LEFT - CHART_PADDING_RIGHT;
+
+        assert_eq!(
+            positions,
+            vec![
+                None,
+                Some((CHART_PADDING_LEFT, plot_width)),
+                Some((CHART_PADDING_LEFT + 0.2 * plot_width, 0.6 * plot_width)),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn height_follows_number_of_lanes() {
+        let rows = [
+            Row::task("a", 0, 10).thread("t1"),
+            Row::task("b", 0, 10).thread("t2"),
+            Row::task("c", 0, 10).thread("t1"),
+            Row::milestone("m", 5).thread("t3"),
+        ];
+        let chart = Chart::new(&rows, &options()).unwrap();
+
+        assert_eq!(
+            chart.height(),
+            CHART_PADDING_TOP + CHART_PADDING_BOTTOM + 3.0 * CHART_THREAD_HEIGHT
+        );
+        assert_eq!(chart.lane_height, CHART_THREAD_HEIGHT);
+
+        let fixed = Chart::new(
+            &rows,
+            &RenderOptions {
+                height: Some(336),
+                ..options()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(fixed.height(), 336.0);
+        assert_eq!(fixed.lane_height, (336.0 - 36.0) / 3.0);
+    }
+
+    #[test]
+    fn markers_take_no_lane() {
+        let mut marker = Row::milestone("deploy", 5);
+
+        marker.kind = RowKind::Marker;
+
+        let chart = Chart::new(&[Row::task("a", 0, 10).thread("t"), marker], &options()).unwrap();
+
+        assert_eq!(chart.lanes, vec!["t"]);
+        assert_eq!(chart.markers.len(), 1);
+        assert_eq!(
+            chart.height(),
+            CHART_PADDING_TOP + CHART_PADDING_BOTTOM + CHART_THREAD_HEIGHT
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::path::Path;
+
+pub type RenderResult<T> = Result<T, RenderError>;
+
+#[derive(Debug)]
+pub enum RenderError {
+    InvalidSize { name: String, min: u32, max: u32 },
+    InvalidTimeWindow { start: i64, end: i64 },
+    InvalidDpi { min: u32, max: u32 },
+    FontNotLoaded { path: String, message: String },
+    ImageNotCreated { width: u32, height: u32 },
+    EncodeError { message: String },
+}
+
+impl RenderError {
+    pub fn invalid_size(name: &str, min: u32, max: u32) -> RenderError {
+        RenderError::InvalidSize {
+            name: name.into(),
+            min,
+            max,
+        }
+    }
+
+    pub fn invalid_time_window(start: i64, end: i64) -> RenderError {
+        RenderError::InvalidTimeWindow { start, end }
+    }
+
+    pub fn invalid_dpi(min: u32, max: u32) -> RenderError {
+        RenderError::InvalidDpi { min, max }
+    }
+
+    pub fn font_not_loaded(path: &Path, message: &str) -> RenderError {
+        warn!("Failed to load font `{}`: {}", path.display(), message);
+
+        RenderError::FontNotLoaded {
+            path: path.display().to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn image_not_created(width: u32, height: u32) -> RenderError {
+        RenderError::ImageNotCreated { width, height }
+    }
+
+    pub fn encode_error(error: png::EncodingError) -> RenderError {
+        RenderError::EncodeError {
+            message: format!("{}", error),
+        }
+    }
+}
+
+impl Error for RenderError {}
+
+impl Display for RenderError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            RenderError::InvalidSize { name, min, max } => {
+                write!(f, "Image {} must be between {} and {}", name, min, max)
+            }
+            RenderError::InvalidTimeWindow { start, end } => write!(
+                f,
+                "Time window start {} must be less than end {}",
+                start, end
+            ),
+            RenderError::InvalidDpi { min, max } => {
+                write!(f, "Image DPI must be between {} and {}", min, max)
+            }
+            RenderError::FontNotLoaded { path, message } => {
+                write!(f, "Failed to load font {}: {}", path, message)
+            }
+            RenderError::ImageNotCreated { width, height } => {
+                write!(f, "Failed to create {}x{} image", width, height)
+            }
+            RenderError::EncodeError { message } => {
+                write!(f, "Failed to encode image: {}", message)
+            }
+        }
+    }
+}
@@ -0,0 +1,130 @@
+mod axis;
+mod canvas;
+mod chart;
+mod error;
+mod png;
+mod svg;
+mod theme;
+
+pub use self::error::RenderError;
+pub use self::error::RenderResult;
+pub use self::theme::Theme;
+pub use ab_glyph::FontVec as Font;
+
+use self::chart::Chart;
+use self::png::PngCanvas;
+use self::svg::SvgCanvas;
+use crate::export::Row;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+const DEFAULT_WIDTH: u32 = 1200;
+const MIN_SIZE: u32 = 240;
+const MAX_SIZE: u32 = 16_384;
+const DEFAULT_DPI: u32 = 96;
+const MIN_DPI: u32 = 24;
+const MAX_DPI: u32 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<ImageFormat, String> {
+        match value {
+            "png" => Ok(ImageFormat::Png),
+            "svg" => Ok(ImageFormat::Svg),
+            _ => Err(format!(
+                "Unknown image format `{}`, expected png or svg",
+                value
+            )),
+        }
+    }
+}
+
+/// Image settings, times are milliseconds since epoch. Missing time window bounds are taken from
+/// data, missing height is computed from number of threads. Size is given in logical pixels,
+/// raster images are scaled by `dpi / 96`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    pub width: u32,
+    pub height: Option<u32>,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub group_mapping: HashMap<String, String>,
+    pub dpi: u32,
+    pub theme: Theme,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            width: DEFAULT_WIDTH,
+            height: None,
+            start: None,
+            end: None,
+            group_mapping: HashMap::new(),
+            dpi: DEFAULT_DPI,
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl RenderOptions {
+    pub fn validate(&self) -> RenderResult<()> {
+        if !(MIN_SIZE..=MAX_SIZE).contains(&self.width) {
+            return Err(RenderError::invalid_size("width", MIN_SIZE, MAX_SIZE));
+        }
+
+        if let Some(height) = self.height {
+            if !(MIN_SIZE..=MAX_SIZE).contains(&height) {
+                return Err(RenderError::invalid_size("height", MIN_SIZE, MAX_SIZE));
+            }
+        }
+
+        if !(MIN_DPI..=MAX_DPI).contains(&self.dpi) {
+            return Err(RenderError::invalid_dpi(MIN_DPI, MAX_DPI));
+        }
+
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            if start >= end {
+                return Err(RenderError::invalid_time_window(start, end));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders rows as standalone SVG document.
+pub fn render_svg(rows: &[Row], options: &RenderOptions) -> RenderResult<String> {
+    let chart = Chart::new(rows, options)?;
+    let mut canvas = SvgCanvas::new(chart.width(), chart.height());
+
+    chart.draw(&mut canvas);
+
+    Ok(canvas.into_document())
+}
+
+/// Renders rows as PNG image using given font for all labels.
+pub fn render_png(rows: &[Row], options: &RenderOptions, font: &Font) -> RenderResult<Vec<u8>> {
+    let chart = Chart::new(rows, options)?;
+    let mut canvas = PngCanvas::new(chart.width(), chart.height(), options.dpi, font)?;
+
+    chart.draw(&mut canvas);
+    canvas.into_png()
+}
+
+/// Reads TrueType or OpenType font file.
+pub fn load_font(path: &Path) -> RenderResult<Font> {
+    let data = fs::read(path).map_err(|e| RenderError::font_not_loaded(path, &e.to_string()))?;
+
+    Font::try_from_vec(data).map_err(|e| RenderError::font_not_loaded(path, &e.to_string()))
+}
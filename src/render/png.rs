@@ -0,0 +1,302 @@
+use super::canvas::Anchor;
+use super::canvas::Canvas;
+use super::canvas::Color;
+use super::canvas::Stroke;
+use super::RenderError;
+use super::RenderResult;
+use ab_glyph::Font;
+use ab_glyph::FontVec;
+use ab_glyph::PxScale;
+use ab_glyph::ScaleFont;
+use png::BitDepth;
+use png::ColorType;
+use png::Encoder;
+use png::PixelDimensions;
+use png::Unit;
+use tiny_skia::FillRule;
+use tiny_skia::Paint;
+use tiny_skia::PathBuilder;
+use tiny_skia::Pixmap;
+use tiny_skia::PremultipliedColorU8;
+use tiny_skia::Rect;
+use tiny_skia::StrokeDash;
+use tiny_skia::Transform;
+
+const FONT_SIZE: f32 = 14.0;
+const BASE_DPI: f32 = 96.0;
+const INCHES_PER_METER: f32 = 39.370_08;
+const MAX_PIXELS: u32 = 32_768;
+const MAX_AREA: u64 = 64 * 1024 * 1024;
+
+/// Canvas which rasterizes chart into RGBA image. Chart coordinates are logical pixels, image is
+/// `dpi / 96` times larger.
+pub struct PngCanvas<'a> {
+    pixmap: Pixmap,
+    dpi: u32,
+    scale: f32,
+    font: &'a FontVec,
+}
+
+impl<'a> PngCanvas<'a> {
+    pub fn new(
+        width: f64,
+        height: f64,
+        dpi: u32,
+        font: &'a FontVec,
+    ) -> RenderResult<PngCanvas<'a>> {
+        let scale = dpi as f32 / BASE_DPI;
+        let (pixel_width, pixel_height) = pixel_size(width, height, scale)?;
+        let pixmap = Pixmap::new(pixel_width, pixel_height)
+            .ok_or_else(|| RenderError::image_not_created(pixel_width, pixel_height))?;
+
+        Ok(PngCanvas {
+            pixmap,
+            dpi,
+            scale,
+            font,
+        })
+    }
+
+    pub fn into_png(self) -> RenderResult<Vec<u8>> {
+        let pixels_per_meter = (self.dpi as f32 * INCHES_PER_METER).round() as u32;
+        let pixels = self.pixmap.pixels();
+        let mut data = Vec::with_capacity(4 * pixels.len());
+
+        for pixel in pixels {
+            let color = pixel.demultiply();
+
+            data.extend_from_slice(&[color.red(), color.green(), color.blue(), color.alpha()]);
+        }
+
+        let mut result = Vec::new();
+        let mut encoder = Encoder::new(&mut result, self.pixmap.width(), self.pixmap.height());
+
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+        encoder.set_pixel_dims(Some(PixelDimensions {
+            xppu: pixels_per_meter,
+            yppu: pixels_per_meter,
+            unit: Unit::Meter,
+        }));
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&data))
+            .map_err(RenderError::encode_error)?;
+
+        Ok(result)
+    }
+
+    fn transform(&self) -> Transform {
+        Transform::from_scale(self.scale, self.scale)
+    }
+
+    fn stroke_path(&mut self, path: &tiny_skia::Path, stroke: Stroke) {
+        let paint = paint(stroke.color);
+        let mut skia_stroke = tiny_skia::Stroke {
+            width: stroke.width as f32,
+            ..tiny_skia::Stroke::default()
+        };
+
+        if let Some((dash, gap)) = stroke.dash {
+            skia_stroke.dash = StrokeDash::new(vec![dash as f32, gap as f32], 0.0);
+        }
+
+        let transform = self.transform();
+
+        self.pixmap
+            .stroke_path(path, &paint, &skia_stroke, transform, None);
+    }
+
+    /// Blends glyph coverage into image, `alpha` is coverage multiplied by color alpha.
+    fn blend_pixel(&mut self, x: i32, y: i32, color: Color, alpha: f32) {
+        if x < 0 || y < 0 || x >= self.pixmap.width() as i32 || y >= self.pixmap.height() as i32 {
+            return;
+        }
+
+        let index = y as usize * self.pixmap.width() as usize + x as usize;
+        let pixels = self.pixmap.pixels_mut();
+        let target = pixels[index];
+        let blend = |source: u8, target: u8| -> u8 {
+            (f32::from(source) * alpha + f32::from(target) * (1.0 - alpha)).round() as u8
+        };
+        let result_alpha = blend(255, target.alpha());
+        let blended = PremultipliedColorU8::from_rgba(
+            blend(color.red, target.red()).min(result_alpha),
+            blend(color.green, target.green()).min(result_alpha),
+            blend(color.blue, target.blue()).min(result_alpha),
+            result_alpha,
+        );
+
+        if let Some(blended) = blended {
+            pixels[index] = blended;
+        }
+    }
+}
+
+impl<'a> Canvas for PngCanvas<'a> {
+    fn fill(&mut self, color: Color) {
+        self.pixmap.fill(skia_color(color));
+    }
+
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), stroke: Stroke) {
+        let mut builder = PathBuilder::new();
+
+        builder.move_to(from.0 as f32, from.1 as f32);
+        builder.line_to(to.0 as f32, to.1 as f32);
+
+        if let Some(path) = builder.finish() {
+            self.stroke_path(&path, stroke);
+        }
+    }
+
+    fn rect(&mut self, origin: (f64, f64), size: (f64, f64), fill: Color, stroke: Stroke) {
+        let rect = match Rect::from_xywh(
+            origin.0 as f32,
+            origin.1 as f32,
+            size.0 as f32,
+            size.1 as f32,
+        ) {
+            Some(rect) => rect,
+            None => return,
+        };
+        let transform = self.transform();
+
+        self.pixmap.fill_rect(rect, &paint(fill), transform, None);
+        self.stroke_path(&PathBuilder::from_rect(rect), stroke);
+    }
+
+    fn polygon(&mut self, points: &[(f64, f64)], fill: Color, stroke: Stroke) {
+        if points.len() < 3 {
+            return;
+        }
+
+        let mut builder = PathBuilder::new();
+
+        builder.move_to(points[0].0 as f32, points[0].1 as f32);
+
+        for point in &points[1..] {
+            builder.line_to(point.0 as f32, point.1 as f32);
+        }
+
+        builder.close();
+
+        let path = match builder.finish() {
+            Some(path) => path,
+            None => return,
+        };
+        let transform = self.transform();
+
+        self.pixmap
+            .fill_path(&path, &paint(fill), FillRule::Winding, transform, None);
+        self.stroke_path(&path, stroke);
+    }
+
+    fn text(&mut self, position: (f64, f64), text: &str, anchor: Anchor, color: Color) {
+        let font = self.font.as_scaled(PxScale::from(FONT_SIZE * self.scale));
+        let width = text_width(&font, text);
+        let mut x = position.0 as f32 * self.scale
+            - match anchor {
+                Anchor::Start => 0.0,
+                Anchor::Middle => width / 2.0,
+                Anchor::End => width,
+            };
+        let y = position.1 as f32 * self.scale;
+        let mut previous = None;
+        let color_alpha = color.opacity() as f32;
+
+        for ch in text.chars() {
+            let glyph_id = font.glyph_id(ch);
+
+            if let Some(previous) = previous {
+                x += font.kern(previous, glyph_id);
+            }
+
+            let glyph = glyph_id.with_scale_and_position(font.scale(), ab_glyph::point(x, y));
+
+            x += font.h_advance(glyph_id);
+            previous = Some(glyph_id);
+
+            if let Some(outline) = self.font.outline_glyph(glyph) {
+                let bounds = outline.px_bounds();
+                let mut coverage = Vec::new();
+
+                outline.draw(|glyph_x, glyph_y, value| {
+                    coverage.push((
+                        bounds.min.x as i32 + glyph_x as i32,
+                        bounds.min.y as i32 + glyph_y as i32,
+                        value,
+                    ));
+                });
+
+                for (pixel_x, pixel_y, value) in coverage {
+                    self.blend_pixel(pixel_x, pixel_y, color, value.min(1.0) * color_alpha);
+                }
+            }
+        }
+    }
+
+    fn text_width(&self, text: &str) -> f64 {
+        let font = self.font.as_scaled(PxScale::from(FONT_SIZE));
+
+        f64::from(text_width(&font, text))
+    }
+}
+
+fn text_width<F: Font, S: ScaleFont<F>>(font: &S, text: &str) -> f32 {
+    let mut width = 0.0;
+    let mut previous = None;
+
+    for ch in text.chars() {
+        let glyph_id = font.glyph_id(ch);
+
+        if let Some(previous) = previous {
+            width += font.kern(previous, glyph_id);
+        }
+
+        width += font.h_advance(glyph_id);
+        previous = Some(glyph_id);
+    }
+
+    width
+}
+
+fn skia_color(color: Color) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba8(color.red, color.green, color.blue, color.alpha)
+}
+
+/// Image size in pixels after DPI scaling. Both sides and the whole area are limited, so memory of
+/// the pixmap and the encoded copy stays bounded.
+fn pixel_size(width: f64, height: f64, scale: f32) -> RenderResult<(u32, u32)> {
+    let pixel_width = (width as f32 * scale).ceil() as u32;
+    let pixel_height = (height as f32 * scale).ceil() as u32;
+
+    if pixel_width > MAX_PIXELS
+        || pixel_height > MAX_PIXELS
+        || pixel_width as u64 * pixel_height as u64 > MAX_AREA
+    {
+        return Err(RenderError::image_not_created(pixel_width, pixel_height));
+    }
+
+    Ok((pixel_width, pixel_height))
+}
+
+fn paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+
+    paint.set_color(skia_color(color));
+    paint.anti_alias = true;
+    paint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixel_size_limits_area() {
+        assert_eq!(pixel_size(1200.0, 800.0, 2.0).unwrap(), (2400, 1600));
+        assert!(pixel_size(16_384.0, 16_384.0, 2.0).is_err());
+        assert!(pixel_size(8_192.0, 8_000.0, 1.0).is_ok());
+        assert!(pixel_size(8_192.0, 8_000.0, 1.5).is_err());
+    }
+}
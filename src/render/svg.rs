@@ -0,0 +1,208 @@
+use super::canvas::Anchor;
+use super::canvas::Canvas;
+use super::canvas::Color;
+use super::canvas::Stroke;
+use std::fmt::Write;
+
+const FONT_FAMILY: &str = "sans-serif";
+const FONT_SIZE: f64 = 14.0;
+// SVG has no text metrics, so widths are estimated using average width of sans-serif glyph.
+const AVERAGE_CHAR_WIDTH: f64 = 0.55 * FONT_SIZE;
+
+/// Canvas which collects drawing commands as SVG elements.
+#[derive(Debug)]
+pub struct SvgCanvas {
+    width: f64,
+    height: f64,
+    body: String,
+}
+
+impl SvgCanvas {
+    pub fn new(width: f64, height: f64) -> SvgCanvas {
+        SvgCanvas {
+            width,
+            height,
+            body: String::new(),
+        }
+    }
+
+    pub fn into_document(self) -> String {
+        let mut document = String::new();
+
+        let _ = writeln!(
+            document,
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}""#,
+                r#" viewBox="0 0 {w} {h}" font-family="{}" font-size="{}">"#,
+            ),
+            FONT_FAMILY,
+            FONT_SIZE,
+            w = self.width,
+            h = self.height,
+        );
+        document.push_str(&self.body);
+        document.push_str("</svg>\n");
+        document
+    }
+}
+
+impl Canvas for SvgCanvas {
+    fn fill(&mut self, color: Color) {
+        let _ = writeln!(
+            self.body,
+            r#"<rect width="100%" height="100%"{}/>"#,
+            color_attributes("fill", color)
+        );
+    }
+
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), stroke: Stroke) {
+        let _ = writeln!(
+            self.body,
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}"{}/>"#,
+            from.0,
+            from.1,
+            to.0,
+            to.1,
+            stroke_attributes(stroke)
+        );
+    }
+
+    fn rect(&mut self, origin: (f64, f64), size: (f64, f64), fill: Color, stroke: Stroke) {
+        let _ = writeln!(
+            self.body,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.2}" height="{:.1}"{}{}/>"#,
+            origin.0,
+            origin.1,
+            size.0,
+            size.1,
+            color_attributes("fill", fill),
+            stroke_attributes(stroke)
+        );
+    }
+
+    fn polygon(&mut self, points: &[(f64, f64)], fill: Color, stroke: Stroke) {
+        if points.len() < 3 {
+            return;
+        }
+
+        let points: Vec<String> = points
+            .iter()
+            .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+            .collect();
+
+        let _ = writeln!(
+            self.body,
+            r#"<polygon points="{}"{}{}/>"#,
+            points.join(" "),
+            color_attributes("fill", fill),
+            stroke_attributes(stroke)
+        );
+    }
+
+    fn text(&mut self, position: (f64, f64), text: &str, anchor: Anchor, color: Color) {
+        let anchor = match anchor {
+            Anchor::Start => "start",
+            Anchor::Middle => "middle",
+            Anchor::End => "end",
+        };
+
+        let _ = writeln!(
+            self.body,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="{}"{}>{}</text>"#,
+            position.0,
+            position.1,
+            anchor,
+            color_attributes("fill", color),
+            escape(text)
+        );
+    }
+
+    fn text_width(&self, text: &str) -> f64 {
+        AVERAGE_CHAR_WIDTH * text.chars().count() as f64
+    }
+}
+
+fn color_attributes(name: &str, color: Color) -> String {
+    if color.alpha == 0xff {
+        format!(r#" {}="{}""#, name, color.hex())
+    } else {
+        format!(
+            r#" {name}="{}" {name}-opacity="{:.3}""#,
+            color.hex(),
+            color.opacity(),
+            name = name
+        )
+    }
+}
+
+fn stroke_attributes(stroke: Stroke) -> String {
+    let mut result = color_attributes("stroke", stroke.color);
+
+    let _ = write!(result, r#" stroke-width="{}""#, stroke.width);
+
+    if let Some((dash, gap)) = stroke.dash {
+        let _ = write!(result, r#" stroke-dasharray="{},{}""#, dash, gap);
+    }
+
+    result
+}
+
+fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match ch {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            ch if ch.is_control() => {}
+            ch => result.push(ch),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::Row;
+    use crate::render::render_svg;
+    use crate::render::RenderOptions;
+
+    #[test]
+    fn text_is_escaped() {
+        assert_eq!(
+            escape("a<b> & \"c\" 'd'\n"),
+            "a&lt;b&gt; &amp; &quot;c&quot; &apos;d&apos;"
+        );
+    }
+
+    #[test]
+    fn transparent_colors_have_opacity() {
+        let color = Color::rgba(0xff, 0x66, 0x66, 0xff);
+
+        assert_eq!(color_attributes("fill", color), r##" fill="#ff6666""##);
+        assert_eq!(
+            color_attributes("fill", color.with_alpha(0.5)),
+            r##" fill="#ff6666" fill-opacity="0.502""##
+        );
+    }
+
+    #[test]
+    fn document_size_follows_chart() {
+        let rows = [
+            Row::task("compile", 0, 1_000).thread("t1"),
+            Row::task("test", 500, 2_000).thread("t2"),
+        ];
+        let document = render_svg(&rows, &RenderOptions::default()).unwrap();
+
+        assert!(document.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="1200" height="100" viewBox="0 0 1200 100""#
+        ));
+        assert!(document.ends_with("</svg>\n"));
+        assert_eq!(document.matches("<rect ").count(), 3);
+    }
+}
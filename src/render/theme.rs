@@ -0,0 +1,56 @@
+use super::canvas::Color;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// Colors of chart elements. Blocks are always light, so text inside blocks stays black.
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    pub background: Color,
+    pub primary: Color,
+    pub secondary: Color,
+    pub block_stroke: Color,
+    pub label: Color,
+    pub block_label: Color,
+}
+
+impl Theme {
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Light => Palette {
+                background: Color::rgba(0xff, 0xff, 0xff, 0xff),
+                primary: Color::rgba(0x00, 0x00, 0x00, 0xff),
+                secondary: Color::rgba(0x00, 0x00, 0x00, 0x40),
+                block_stroke: Color::rgba(0x00, 0x00, 0x00, 0x60),
+                label: Color::rgba(0x00, 0x00, 0x00, 0xff),
+                block_label: Color::rgba(0x00, 0x00, 0x00, 0xff),
+            },
+            Theme::Dark => Palette {
+                background: Color::rgba(0x1e, 0x1e, 0x1e, 0xff),
+                primary: Color::rgba(0xe0, 0xe0, 0xe0, 0xff),
+                secondary: Color::rgba(0xff, 0xff, 0xff, 0x40),
+                block_stroke: Color::rgba(0x00, 0x00, 0x00, 0x90),
+                label: Color::rgba(0xe0, 0xe0, 0xe0, 0xff),
+                block_label: Color::rgba(0x00, 0x00, 0x00, 0xff),
+            },
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Theme, String> {
+        match value {
+            "light" => Ok(Theme::Light),
+            "dark" => Ok(Theme::Dark),
+            _ => Err(format!("Unknown theme `{}`, expected light or dark", value)),
+        }
+    }
+}
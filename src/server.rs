@@ -1,21 +1,56 @@
+use crate::config::Config;
+use crate::handlers::CompareHandler;
+use crate::handlers::CriticalPathHandler;
 use crate::handlers::DataHandler;
 use crate::handlers::DatabasesHandler;
+use crate::handlers::InferredPathHandler;
+use crate::handlers::PngHandler;
+use crate::handlers::ProfilesHandler;
+use crate::handlers::QualityHandler;
+use crate::handlers::SchemaHandler;
+use crate::handlers::SimulateHandler;
 use crate::handlers::StagesHandler;
-use crate::options::Options;
+use crate::handlers::StatsHandler;
+use crate::handlers::StreamHandler;
+use crate::handlers::SvgHandler;
+use crate::handlers::TailHandler;
+use crate::handlers::TrendHandler;
+use crate::handlers::UtilizationHandler;
+use crate::render::Font;
 use iron::Iron;
 use mount::Mount;
 use staticfile::Static;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
-pub fn start(options: &Options) -> () {
+pub fn start(config: Config, font: Font) {
+    let config = Arc::new(config);
+    let server = config.server();
+    let static_dir = &server.static_dir;
     let mut mount = Mount::new();
-    mount.mount("/api/v1/databases", DatabasesHandler::new());
-    mount.mount("/api/v1/stages", StagesHandler::new());
-    mount.mount("/api/v1/data", DataHandler::new(options.temp_dir()));
-    mount.mount("/static", Static::new("public/static"));
-    mount.mount("/", Static::new("public"));
+    mount.mount("/api/v1/profiles", ProfilesHandler::new(&config));
+    mount.mount("/api/v1/databases", DatabasesHandler::new(&config));
+    mount.mount("/api/v1/schema", SchemaHandler::new(&config));
+    mount.mount("/api/v1/stages", StagesHandler::new(&config));
+    mount.mount("/api/v1/data", DataHandler::new(&config, &server.temp_dir));
+    mount.mount("/api/v1/stats", StatsHandler::new(&config));
+    mount.mount("/api/v1/quality", QualityHandler::new(&config));
+    mount.mount("/api/v1/utilization", UtilizationHandler::new(&config));
+    mount.mount("/api/v1/critical-path", CriticalPathHandler::new(&config));
+    mount.mount("/api/v1/inferred-path", InferredPathHandler::new(&config));
+    mount.mount("/api/v1/simulate", SimulateHandler::new(&config));
+    mount.mount("/api/v1/compare", CompareHandler::new(&config));
+    mount.mount("/api/v1/trend", TrendHandler::new(&config));
+    mount.mount("/api/v1/tail", TailHandler::new(&config));
+    mount.mount("/api/v1/tail/stream", StreamHandler::new(&config));
+    mount.mount("/api/v1/render.svg", SvgHandler::new(&config));
+    mount.mount("/api/v1/render.png", PngHandler::new(&config, font));
+    mount.mount("/static", static_files(&config, &static_dir.join("static")));
+    mount.mount("/", static_files(&config, static_dir));
 
-    let address = options.address();
-    let port = options.port();
+    let address = server.address.as_str();
+    let port = server.port;
 
     println!("Listening on {}:{}...", address, port);
 
@@ -24,3 +59,13 @@ pub fn start(options: &Options) -> () {
         Err(err) => error!("Failed to start HTTP server: {}", err),
     }
 }
+
+fn static_files(config: &Config, path: &Path) -> Static {
+    let max_age = config.cache().static_max_age;
+
+    if max_age > 0 {
+        Static::new(path).cache(Duration::from_secs(max_age))
+    } else {
+        Static::new(path)
+    }
+}
@@ -0,0 +1,330 @@
+use crate::database::DatabaseResult;
+use crate::database::PostgreSQL;
+use crate::export;
+use crate::export::Row;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+/// Differences between two runs of the same stage. Deltas are `current - base`, so positive
+/// values are regressions. Offsets are relative to the start of the stage.
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub base: StageSummary,
+    pub current: StageSummary,
+    pub span_delta: i64,
+    pub task_time_delta: i64,
+    pub targets: Vec<TargetDelta>,
+    pub only_base: Vec<Row>,
+    pub only_current: Vec<Row>,
+    pub groups: Vec<GroupDelta>,
+}
+
+/// `targets` is the number of all compared tasks including nested ones, because every task is
+/// matched. `task_time` sums only top-level tasks, nested tasks are covered by their parents.
+#[derive(Debug, Serialize)]
+pub struct StageSummary {
+    pub database: String,
+    pub stage: String,
+    pub targets: usize,
+    pub start: Option<i64>,
+    pub span: i64,
+    pub task_time: i64,
+}
+
+/// Targets are matched by name, targets with the same name are matched in order of start time
+/// and `occurrence` is their index.
+#[derive(Debug, Serialize)]
+pub struct TargetDelta {
+    pub name: String,
+    pub occurrence: usize,
+    pub group: String,
+    pub base_offset: i64,
+    pub current_offset: i64,
+    pub offset_delta: i64,
+    pub base_duration: i64,
+    pub current_duration: i64,
+    pub duration_delta: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupDelta {
+    pub name: String,
+    pub base_time: i64,
+    pub current_time: i64,
+    pub delta: i64,
+}
+
+/// Reads both stages and compares them, stages may be in different databases of the same server.
+pub fn compare(
+    postgres: &PostgreSQL,
+    base: (&str, &str),
+    current: (&str, &str),
+) -> DatabaseResult<Comparison> {
+    let mut base_rows = export::read_rows(postgres, base.0, base.1)?;
+    let mut current_rows = export::read_rows(postgres, current.0, current.1)?;
+
+    // Milestones take no time and have nothing to compare.
+    base_rows.retain(Row::is_task);
+    current_rows.retain(Row::is_task);
+
+    Ok(Comparison::new(
+        summary(base, &base_rows),
+        &base_rows,
+        summary(current, &current_rows),
+        &current_rows,
+    ))
+}
+
+impl Comparison {
+    fn new(
+        base: StageSummary,
+        base_rows: &[Row],
+        current: StageSummary,
+        current_rows: &[Row],
+    ) -> Comparison {
+        let base_start = base.start.unwrap_or(0);
+        let current_start = current.start.unwrap_or(0);
+        let mut base_targets = by_name(base_rows);
+        let mut targets = Vec::new();
+        let mut only_current = Vec::new();
+
+        for ((name, occurrence), current_row) in by_name(current_rows) {
+            match base_targets.remove(&(name, occurrence)) {
+                Some(base_row) => {
+                    let base_offset = base_row.start - base_start;
+                    let current_offset = current_row.start - current_start;
+                    let base_duration = base_row.end - base_row.start;
+                    let current_duration = current_row.end - current_row.start;
+
+                    targets.push(TargetDelta {
+                        name: name.into(),
+                        occurrence,
+                        group: current_row.group.clone(),
+                        base_offset,
+                        current_offset,
+                        offset_delta: current_offset - base_offset,
+                        base_duration,
+                        current_duration,
+                        duration_delta: current_duration - base_duration,
+                    });
+                }
+                None => only_current.push(current_row.clone()),
+            }
+        }
+
+        let mut only_base: Vec<Row> = base_targets.into_values().cloned().collect();
+
+        targets.sort_by_key(|target| (Reverse(target.duration_delta), target.current_offset));
+        only_base.sort_by_key(|row| row.start);
+        only_current.sort_by_key(|row| row.start);
+
+        Comparison {
+            span_delta: current.span - base.span,
+            task_time_delta: current.task_time - base.task_time,
+            base,
+            current,
+            targets,
+            only_base,
+            only_current,
+            groups: group_deltas(base_rows, current_rows),
+        }
+    }
+}
+
+fn summary(stage: (&str, &str), rows: &[Row]) -> StageSummary {
+    let start = rows.iter().map(|row| row.start).min();
+    let end = rows.iter().map(|row| row.end).max();
+
+    StageSummary {
+        database: stage.0.into(),
+        stage: stage.1.into(),
+        targets: rows.len(),
+        start,
+        span: end.unwrap_or(0) - start.unwrap_or(0),
+        task_time: top_level(rows).map(|row| row.end - row.start).sum(),
+    }
+}
+
+/// Indexes rows by name and occurrence of the name in order of start time.
+fn by_name(rows: &[Row]) -> BTreeMap<(&str, usize), &Row> {
+    let mut sorted: Vec<&Row> = rows.iter().collect();
+    let mut occurrences: HashMap<&str, usize> = HashMap::new();
+    let mut result = BTreeMap::new();
+
+    sorted.sort_by_key(|row| row.start);
+
+    for row in sorted {
+        let occurrence = occurrences.entry(row.name.as_str()).or_insert(0);
+
+        result.insert((row.name.as_str(), *occurrence), row);
+
+        *occurrence += 1;
+    }
+
+    result
+}
+
+/// Nested targets are covered by their top-level target, so they are not added to totals.
+fn top_level(rows: &[Row]) -> impl Iterator<Item = &Row> {
+    rows.iter().filter(|row| row.depth == 0)
+}
+
+fn group_deltas(base_rows: &[Row], current_rows: &[Row]) -> Vec<GroupDelta> {
+    let mut totals: BTreeMap<&str, (i64, i64)> = BTreeMap::new();
+
+    for row in top_level(base_rows) {
+        totals.entry(row.group.as_str()).or_default().0 += row.end - row.start;
+    }
+
+    for row in top_level(current_rows) {
+        totals.entry(row.group.as_str()).or_default().1 += row.end - row.start;
+    }
+
+    let mut result: Vec<GroupDelta> = totals
+        .into_iter()
+        .map(|(name, (base_time, current_time))| GroupDelta {
+            name: name.into(),
+            base_time,
+            current_time,
+            delta: current_time - base_time,
+        })
+        .collect();
+
+    result.sort_by_key(|group| Reverse(group.delta));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compare_rows(base_rows: &[Row], current_rows: &[Row]) -> Comparison {
+        Comparison::new(
+            summary(("db", "base"), base_rows),
+            base_rows,
+            summary(("db", "current"), current_rows),
+            current_rows,
+        )
+    }
+
+    #[test]
+    fn same_names_are_matched_by_occurrence() {
+        let rows = [
+            Row::task("test", 30, 40),
+            Row::task("compile", 0, 10),
+            Row::task("test", 10, 30),
+        ];
+        let indexed = by_name(&rows);
+
+        assert_eq!(indexed.len(), 3);
+        assert_eq!(indexed[&("test", 0)].start, 10);
+        assert_eq!(indexed[&("test", 1)].start, 30);
+        assert_eq!(indexed[&("compile", 0)].start, 0);
+    }
+
+    #[test]
+    fn unmatched_targets_are_listed_by_start() {
+        let comparison = compare_rows(
+            &[
+                Row::task("test", 0, 10),
+                Row::task("lint", 20, 30),
+                Row::task("docs", 10, 20),
+            ],
+            &[
+                Row::task("test", 100, 110),
+                Row::task("test", 120, 130),
+                Row::task("package", 110, 120),
+            ],
+        );
+        let names = |rows: &[Row]| -> Vec<(String, i64)> {
+            rows.iter()
+                .map(|row| (row.name.clone(), row.start))
+                .collect()
+        };
+
+        assert_eq!(comparison.targets.len(), 1);
+        assert_eq!(
+            names(&comparison.only_base),
+            vec![("docs".into(), 10), ("lint".into(), 20)]
+        );
+        assert_eq!(
+            names(&comparison.only_current),
+            vec![("package".into(), 110), ("test".into(), 120)]
+        );
+    }
+
+    #[test]
+    fn largest_regressions_first() {
+        let comparison = compare_rows(
+            &[
+                Row::task("a", 0, 10),
+                Row::task("b", 0, 10),
+                Row::task("c", 10, 20),
+                Row::task("d", 20, 30),
+            ],
+            &[
+                Row::task("a", 100, 105),
+                Row::task("b", 100, 130),
+                Row::task("c", 130, 140),
+                Row::task("d", 120, 130),
+            ],
+        );
+        let order: Vec<(&str, i64, i64)> = comparison
+            .targets
+            .iter()
+            .map(|target| {
+                (
+                    target.name.as_str(),
+                    target.duration_delta,
+                    target.offset_delta,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            order,
+            vec![("b", 20, 0), ("d", 0, 0), ("c", 0, 20), ("a", -5, 0)]
+        );
+    }
+
+    #[test]
+    fn group_deltas_count_top_level_targets() {
+        let comparison = compare_rows(
+            &[
+                Row::task("compile", 0, 10).group("build"),
+                Row::task("test", 10, 20).group("test"),
+            ],
+            &[
+                Row::task("compile", 0, 30).group("build"),
+                Row::task("object", 0, 25).group("build").depth(1),
+                Row::task("lint", 30, 35).group("check"),
+            ],
+        );
+        let groups: Vec<(&str, i64, i64, i64)> = comparison
+            .groups
+            .iter()
+            .map(|group| {
+                (
+                    group.name.as_str(),
+                    group.base_time,
+                    group.current_time,
+                    group.delta,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            groups,
+            vec![
+                ("build", 10, 30, 20),
+                ("check", 0, 5, 5),
+                ("test", 10, 0, -10)
+            ]
+        );
+        assert_eq!(comparison.current.targets, 3);
+        assert_eq!(comparison.current.task_time, 35);
+        assert_eq!(comparison.task_time_delta, 15);
+        assert_eq!(comparison.span_delta, 15);
+    }
+}
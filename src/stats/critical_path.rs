@@ -0,0 +1,386 @@
+use crate::database::DatabaseResult;
+use crate::database::Dependency;
+use crate::database::PostgreSQL;
+use crate::export;
+use crate::export::Row;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+
+/// Longest chain of dependent targets computed with critical path method from target durations.
+/// `length` is the time the stage would take with unlimited threads, `span` is its actual
+/// wall-clock duration.
+#[derive(Debug, Serialize)]
+pub struct CriticalPath {
+    pub length: i64,
+    pub span: i64,
+    pub dependencies: usize,
+    pub path: Vec<Row>,
+    pub targets: Vec<TargetSlack>,
+    pub unknown_prerequisites: Vec<String>,
+    pub cyclic: Vec<String>,
+}
+
+/// Earliest and latest start of the target relative to the stage start. Slack is how much the
+/// target can be delayed without making the stage longer, critical targets have no slack.
+#[derive(Debug, Serialize)]
+pub struct TargetSlack {
+    pub name: String,
+    pub group: String,
+    pub thread: String,
+    pub start: i64,
+    pub duration: i64,
+    pub earliest_start: i64,
+    pub latest_start: i64,
+    pub slack: i64,
+    pub critical: bool,
+}
+
+/// Edges between rows, prerequisites by name are resolved to every target with that name.
+#[derive(Debug)]
+pub(super) struct Graph {
+    pub predecessors: Vec<Vec<usize>>,
+    pub successors: Vec<Vec<usize>>,
+    pub edges: usize,
+    pub unknown_prerequisites: Vec<String>,
+}
+
+impl Graph {
+    pub fn new(rows: &[Row], dependencies: &[Dependency]) -> Graph {
+        let mut names: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut edges = BTreeSet::new();
+        let mut unknown_prerequisites = BTreeSet::new();
+
+        for (index, row) in rows.iter().enumerate() {
+            names.entry(&row.name).or_default().push(index);
+        }
+
+        for dependency in dependencies {
+            if dependency.target == dependency.prerequisite {
+                continue;
+            }
+
+            let targets = match names.get(dependency.target.as_str()) {
+                Some(targets) => targets,
+                None => continue,
+            };
+            let prerequisites = match names.get(dependency.prerequisite.as_str()) {
+                Some(prerequisites) => prerequisites,
+                None => {
+                    unknown_prerequisites.insert(dependency.prerequisite.clone());
+                    continue;
+                }
+            };
+
+            for &target in targets {
+                for &prerequisite in prerequisites {
+                    edges.insert((prerequisite, target));
+                }
+            }
+        }
+
+        let mut predecessors = vec![Vec::new(); rows.len()];
+        let mut successors = vec![Vec::new(); rows.len()];
+
+        for &(from, to) in &edges {
+            predecessors[to].push(from);
+            successors[from].push(to);
+        }
+
+        Graph {
+            predecessors,
+            successors,
+            edges: edges.len(),
+            unknown_prerequisites: unknown_prerequisites.into_iter().collect(),
+        }
+    }
+
+    /// Kahn's topological sort. Targets in cycles and targets depending on them are missing.
+    fn order(&self) -> Vec<usize> {
+        let mut incoming: Vec<usize> = self.predecessors.iter().map(Vec::len).collect();
+        let mut queue: VecDeque<usize> = (0..incoming.len())
+            .filter(|&index| incoming[index] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(incoming.len());
+
+        while let Some(index) = queue.pop_front() {
+            order.push(index);
+
+            for &successor in &self.successors[index] {
+                incoming[successor] -= 1;
+
+                if incoming[successor] == 0 {
+                    queue.push_back(successor);
+                }
+            }
+        }
+
+        order
+    }
+}
+
+/// Reads targets and dependencies of the stage and computes its critical path.
+pub fn critical_path(
+    postgres: &PostgreSQL,
+    database: &str,
+    stage: &str,
+) -> DatabaseResult<CriticalPath> {
+    let mut rows = export::read_rows(postgres, database, stage)?;
+
+    rows.retain(Row::is_task);
+
+    let dependencies = postgres.dependencies(database, stage)?;
+    let (critical_path, _) = analyze(&rows, &dependencies);
+
+    Ok(critical_path)
+}
+
+/// Same as `critical_path`, but returns all rows of the stage with `critical` flag set. Milestones
+/// are never critical.
+pub fn critical_rows(
+    postgres: &PostgreSQL,
+    database: &str,
+    stage: &str,
+) -> DatabaseResult<Vec<Row>> {
+    let mut rows = export::read_rows(postgres, database, stage)?;
+    let tasks: Vec<Row> = rows.iter().filter(|row| row.is_task()).cloned().collect();
+    let dependencies = postgres.dependencies(database, stage)?;
+    let (_, critical) = analyze(&tasks, &dependencies);
+    let tasks = rows.iter_mut().filter(|row| row.is_task());
+
+    for (row, critical) in tasks.zip(critical) {
+        row.critical = critical;
+    }
+
+    Ok(rows)
+}
+
+/// Returns critical path and critical flag of every row.
+fn analyze(rows: &[Row], dependencies: &[Dependency]) -> (CriticalPath, Vec<bool>) {
+    let graph = Graph::new(rows, dependencies);
+    let order = graph.order();
+    let durations: Vec<i64> = rows.iter().map(|row| row.end - row.start).collect();
+    let mut earliest_start = vec![0; rows.len()];
+    let mut earliest_end = vec![0; rows.len()];
+
+    for &index in &order {
+        earliest_start[index] = graph.predecessors[index]
+            .iter()
+            .map(|&predecessor| earliest_end[predecessor])
+            .max()
+            .unwrap_or(0);
+        earliest_end[index] = earliest_start[index] + durations[index];
+    }
+
+    let length = order
+        .iter()
+        .map(|&index| earliest_end[index])
+        .max()
+        .unwrap_or(0);
+    let mut resolved = vec![false; rows.len()];
+
+    for &index in &order {
+        resolved[index] = true;
+    }
+
+    let mut latest_start = vec![0; rows.len()];
+
+    // Successors in cycles have no latest start, they must not shorten their prerequisites.
+    for &index in order.iter().rev() {
+        let latest_end = graph.successors[index]
+            .iter()
+            .filter(|&&successor| resolved[successor])
+            .map(|&successor| latest_start[successor])
+            .min()
+            .unwrap_or(length);
+
+        latest_start[index] = latest_end - durations[index];
+    }
+
+    let mut critical = vec![false; rows.len()];
+
+    for &index in &order {
+        critical[index] = latest_start[index] == earliest_start[index];
+    }
+
+    let cyclic: BTreeSet<_> = (0..rows.len())
+        .filter(|&index| !resolved[index])
+        .map(|index| rows[index].name.clone())
+        .collect();
+    let path = longest_chain(&graph, &order, &earliest_start, &earliest_end)
+        .into_iter()
+        .map(|index| {
+            let mut row = rows[index].clone();
+
+            row.critical = true;
+            row
+        })
+        .collect();
+    let mut targets: Vec<_> = order
+        .iter()
+        .map(|&index| {
+            let row = &rows[index];
+            let slack = latest_start[index] - earliest_start[index];
+
+            TargetSlack {
+                name: row.name.clone(),
+                group: row.group.clone(),
+                thread: row.thread.clone(),
+                start: row.start,
+                duration: durations[index],
+                earliest_start: earliest_start[index],
+                latest_start: latest_start[index],
+                slack,
+                critical: critical[index],
+            }
+        })
+        .collect();
+
+    targets.sort_by_key(|target| (target.slack, target.earliest_start, target.start));
+
+    let critical_path = CriticalPath {
+        length,
+        span: span(rows),
+        dependencies: graph.edges,
+        path,
+        targets,
+        unknown_prerequisites: graph.unknown_prerequisites,
+        cyclic: cyclic.into_iter().collect(),
+    };
+
+    (critical_path, critical)
+}
+
+/// Walks back from the target finishing last through predecessors which finish exactly when the
+/// next target can start.
+fn longest_chain(
+    graph: &Graph,
+    order: &[usize],
+    earliest_start: &[i64],
+    earliest_end: &[i64],
+) -> Vec<usize> {
+    let mut chain = Vec::new();
+    let mut current = order
+        .iter()
+        .copied()
+        .max_by_key(|&index| (earliest_end[index], Reverse(index)));
+
+    while let Some(index) = current {
+        chain.push(index);
+        current = graph.predecessors[index]
+            .iter()
+            .copied()
+            .find(|&predecessor| earliest_end[predecessor] == earliest_start[index]);
+    }
+
+    chain.reverse();
+    chain
+}
+
+fn span(rows: &[Row]) -> i64 {
+    let start = rows.iter().map(|row| row.start).min();
+    let end = rows.iter().map(|row| row.end).max();
+
+    match (start, end) {
+        (Some(start), Some(end)) => end - start,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slack(critical_path: &CriticalPath, name: &str) -> i64 {
+        critical_path
+            .targets
+            .iter()
+            .find(|target| target.name == name)
+            .map(|target| target.slack)
+            .unwrap()
+    }
+
+    #[test]
+    fn empty_stage() {
+        let (critical_path, critical) = analyze(&[], &[]);
+
+        assert_eq!(critical_path.length, 0);
+        assert_eq!(critical_path.span, 0);
+        assert!(critical_path.path.is_empty());
+        assert!(critical.is_empty());
+    }
+
+    #[test]
+    fn longest_chain_is_critical() {
+        let rows = [
+            Row::task("compile", 0, 50),
+            Row::task("docs", 0, 20),
+            Row::task("link", 50, 80),
+            Row::task("package", 80, 90),
+        ];
+        let dependencies = [
+            Dependency::new("link", "compile"),
+            Dependency::new("package", "link"),
+            Dependency::new("package", "docs"),
+        ];
+        let (critical_path, critical) = analyze(&rows, &dependencies);
+        let path: Vec<&str> = critical_path
+            .path
+            .iter()
+            .map(|row| row.name.as_str())
+            .collect();
+
+        assert_eq!(critical_path.length, 90);
+        assert_eq!(critical_path.dependencies, 3);
+        assert_eq!(path, vec!["compile", "link", "package"]);
+        assert_eq!(critical, vec![true, false, true, true]);
+        assert_eq!(slack(&critical_path, "docs"), 60);
+    }
+
+    #[test]
+    fn cycles_do_not_change_slack_of_prerequisites() {
+        let rows = [
+            Row::task("compile", 0, 50),
+            Row::task("docs", 0, 20),
+            Row::task("a", 50, 60),
+            Row::task("b", 60, 70),
+        ];
+        let dependencies = [
+            Dependency::new("a", "docs"),
+            Dependency::new("a", "b"),
+            Dependency::new("b", "a"),
+        ];
+        let (critical_path, critical) = analyze(&rows, &dependencies);
+
+        assert_eq!(critical_path.cyclic, vec!["a", "b"]);
+        assert_eq!(critical_path.length, 50);
+        assert_eq!(slack(&critical_path, "docs"), 30);
+        assert_eq!(critical, vec![true, false, false, false]);
+    }
+
+    #[test]
+    fn unknown_prerequisites_and_self_dependencies_are_ignored() {
+        let rows = [Row::task("a", 0, 10), Row::task("b", 10, 20)];
+        let dependencies = [
+            Dependency::new("a", "a"),
+            Dependency::new("b", "missing"),
+            Dependency::new("b", "a"),
+        ];
+        let (critical_path, _) = analyze(&rows, &dependencies);
+
+        assert_eq!(critical_path.dependencies, 1);
+        assert_eq!(critical_path.unknown_prerequisites, vec!["missing"]);
+        assert_eq!(critical_path.length, 20);
+    }
+
+    #[test]
+    fn ties_pick_the_first_target() {
+        let rows = [Row::task("a", 0, 10), Row::task("b", 0, 10)];
+        let (critical_path, critical) = analyze(&rows, &[]);
+
+        assert_eq!(critical_path.path.len(), 1);
+        assert_eq!(critical_path.path[0].name, "a");
+        assert_eq!(critical, vec![true, true]);
+    }
+}
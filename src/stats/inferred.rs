@@ -0,0 +1,279 @@
+use super::percent;
+use crate::database::DatabaseResult;
+use crate::database::PostgreSQL;
+use crate::export;
+use crate::export::Row;
+use std::str::FromStr;
+
+pub const DEFAULT_TOLERANCE: i64 = 1_000;
+pub const MAX_TOLERANCE: i64 = 3_600_000;
+
+/// Threads where predecessors of a target are looked for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InferenceScope {
+    #[default]
+    Any,
+    Thread,
+}
+
+impl FromStr for InferenceScope {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<InferenceScope, String> {
+        match value {
+            "any" => Ok(InferenceScope::Any),
+            "thread" => Ok(InferenceScope::Thread),
+            _ => Err(format!(
+                "Unknown inference scope `{}`, expected any or thread",
+                value
+            )),
+        }
+    }
+}
+
+/// Chain of targets explaining wall-clock time of the stage when dependencies are not recorded.
+/// A target is assumed to depend on targets which finished at most `tolerance` milliseconds before
+/// it started, the chain is built backwards from the target finished last. `coverage` is the
+/// percent of the stage span covered by targets of the chain.
+#[derive(Debug, Serialize)]
+pub struct InferredPath {
+    pub tolerance: i64,
+    pub scope: InferenceScope,
+    pub span: i64,
+    pub edges: usize,
+    pub length: i64,
+    pub busy_time: i64,
+    pub coverage: f64,
+    pub unexplained_start: i64,
+    pub ambiguous: usize,
+    pub chain: Vec<ChainLink>,
+    pub assumptions: Vec<String>,
+}
+
+/// Target of the inferred chain. `gap` is the time between the end of the previous target of the
+/// chain and the start of this one, `candidates` is the number of targets which could be its
+/// predecessor.
+#[derive(Debug, Serialize)]
+pub struct ChainLink {
+    #[serde(flatten)]
+    pub row: Row,
+    pub gap: i64,
+    pub candidates: usize,
+}
+
+/// Reads targets of the stage and infers the chain of targets explaining its wall-clock time.
+/// Nested targets are ignored, they are explained by their top-level target.
+pub fn inferred_path(
+    postgres: &PostgreSQL,
+    database: &str,
+    stage: &str,
+    tolerance: i64,
+    scope: InferenceScope,
+) -> DatabaseResult<InferredPath> {
+    let mut rows = export::read_rows(postgres, database, stage)?;
+
+    rows.retain(|row| row.depth == 0);
+
+    Ok(infer(&rows, tolerance, scope))
+}
+
+fn infer(rows: &[Row], tolerance: i64, scope: InferenceScope) -> InferredPath {
+    let mut by_end: Vec<usize> = (0..rows.len()).collect();
+
+    by_end.sort_by_key(|&index| rows[index].end);
+
+    // Candidates of every target ordered by end time, the latest finished is the last one. The
+    // predecessor must be less by (start, end), so zero-length targets at the same time can not
+    // precede each other and the chain always ends.
+    let predecessors: Vec<Vec<usize>> = rows
+        .iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let first = by_end.partition_point(|&other| rows[other].end < row.start - tolerance);
+            let last = by_end.partition_point(|&other| rows[other].end <= row.start);
+
+            by_end[first..last]
+                .iter()
+                .copied()
+                .filter(|&other| other != index)
+                .filter(|&other| (rows[other].start, rows[other].end) < (row.start, row.end))
+                .filter(|&other| scope == InferenceScope::Any || rows[other].thread == row.thread)
+                .collect()
+        })
+        .collect();
+    let edges = predecessors.iter().map(Vec::len).sum();
+    let mut chain = Vec::new();
+    let mut current = by_end.last().copied();
+
+    while let Some(index) = current {
+        let previous = predecessors[index].last().copied();
+        let gap = previous.map_or(0, |previous| rows[index].start - rows[previous].end);
+
+        let mut row = rows[index].clone();
+
+        row.critical = true;
+        chain.push(ChainLink {
+            row,
+            gap,
+            candidates: predecessors[index].len(),
+        });
+        current = previous;
+    }
+
+    chain.reverse();
+
+    let start = rows.iter().map(|row| row.start).min().unwrap_or(0);
+    let end = rows.iter().map(|row| row.end).max().unwrap_or(0);
+    let span = end - start;
+    let length = match (chain.first(), chain.last()) {
+        (Some(first), Some(last)) => last.row.end - first.row.start,
+        _ => 0,
+    };
+    let busy_time = chain.iter().map(|link| link.row.end - link.row.start).sum();
+    let unexplained_start = chain.first().map_or(0, |first| first.row.start - start);
+    let ambiguous = chain.iter().filter(|link| link.candidates > 1).count();
+
+    InferredPath {
+        tolerance,
+        scope,
+        span,
+        edges,
+        length,
+        busy_time,
+        coverage: percent(busy_time, span),
+        unexplained_start,
+        ambiguous,
+        chain,
+        assumptions: assumptions(tolerance, scope, rows.iter().any(|row| row.running)),
+    }
+}
+
+fn assumptions(tolerance: i64, scope: InferenceScope, running: bool) -> Vec<String> {
+    let mut assumptions = vec![
+        format!(
+            "Target depends on targets finished at most {} ms before it started",
+            tolerance
+        ),
+        match scope {
+            InferenceScope::Any => "Predecessors are looked for on all threads".to_string(),
+            InferenceScope::Thread => "Predecessors are looked for on the same thread".to_string(),
+        },
+        "When several targets could be the predecessor, the one finished last is chosen"
+            .to_string(),
+        "Chain stops at the first target without predecessor, time before it is unexplained"
+            .to_string(),
+    ];
+
+    if running {
+        assumptions.push("Running targets are treated as finished at the time of query".into());
+    }
+
+    assumptions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(path: &InferredPath) -> Vec<&str> {
+        path.chain
+            .iter()
+            .map(|link| link.row.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn empty_stage() {
+        let path = infer(&[], DEFAULT_TOLERANCE, InferenceScope::Any);
+
+        assert!(path.chain.is_empty());
+        assert_eq!(path.span, 0);
+        assert_eq!(path.coverage, 0.0);
+    }
+
+    #[test]
+    fn zero_length_rows_at_same_time_end_chain() {
+        let rows = [
+            Row::task("a", 10, 10).thread("t1"),
+            Row::task("b", 10, 10).thread("t2"),
+            Row::task("c", 10, 20).thread("t1"),
+        ];
+        let path = infer(&rows, DEFAULT_TOLERANCE, InferenceScope::Any);
+
+        assert_eq!(path.chain.len(), 2);
+        assert_eq!(path.chain[1].row.name, "c");
+        assert_eq!(path.chain[1].candidates, 2);
+    }
+
+    #[test]
+    fn chain_follows_latest_finished_predecessor() {
+        let rows = [
+            Row::task("a", 0, 100).thread("t1"),
+            Row::task("b", 0, 150).thread("t2"),
+            Row::task("c", 150, 300).thread("t1"),
+            Row::task("d", 5_000, 6_000).thread("t1"),
+        ];
+        let path = infer(&rows, DEFAULT_TOLERANCE, InferenceScope::Any);
+
+        assert_eq!(names(&path), ["d"]);
+
+        let path = infer(&rows[..3], DEFAULT_TOLERANCE, InferenceScope::Any);
+
+        assert_eq!(names(&path), ["b", "c"]);
+        assert_eq!(path.ambiguous, 1);
+
+        let path = infer(&rows[..3], DEFAULT_TOLERANCE, InferenceScope::Thread);
+
+        assert_eq!(names(&path), ["a", "c"]);
+        assert_eq!(path.chain[1].gap, 50);
+    }
+
+    #[test]
+    fn ties_choose_the_last_listed_target() {
+        let rows = [
+            Row::task("a", 0, 100).thread("t1"),
+            Row::task("b", 0, 100).thread("t2"),
+            Row::task("c", 100, 200).thread("t1"),
+            Row::task("d", 150, 200).thread("t2"),
+        ];
+        let path = infer(&rows, DEFAULT_TOLERANCE, InferenceScope::Any);
+
+        assert_eq!(names(&path), ["b", "d"]);
+        assert_eq!(path.chain[1].gap, 50);
+        assert_eq!(path.edges, 4);
+    }
+
+    #[test]
+    fn coverage_and_unexplained_start() {
+        let rows = [
+            Row::task("setup", 0, 50).thread("t1"),
+            Row::task("compile", 100, 200).thread("t1"),
+            Row::task("link", 200, 300).thread("t1"),
+        ];
+        let path = infer(&rows, 10, InferenceScope::Any);
+
+        assert_eq!(names(&path), ["compile", "link"]);
+        assert_eq!(path.span, 300);
+        assert_eq!(path.length, 200);
+        assert_eq!(path.busy_time, 200);
+        assert_eq!(path.unexplained_start, 100);
+        assert!((path.coverage - 200.0 / 3.0).abs() < 1e-9);
+        assert_eq!(path.assumptions.len(), 4);
+    }
+
+    #[test]
+    fn running_targets_are_assumed_finished() {
+        let running = Row::task("a", 0, 100).thread("t1").running();
+        let path = infer(&[running], DEFAULT_TOLERANCE, InferenceScope::Any);
+
+        assert_eq!(path.assumptions.len(), 5);
+    }
+
+    #[test]
+    fn scope_is_parsed() {
+        assert_eq!("any".parse(), Ok(InferenceScope::Any));
+        assert_eq!("thread".parse(), Ok(InferenceScope::Thread));
+        assert!("group".parse::<InferenceScope>().is_err());
+    }
+}
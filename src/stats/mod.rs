@@ -0,0 +1,532 @@
+mod compare;
+mod critical_path;
+mod inferred;
+mod simulation;
+mod trend;
+mod utilization;
+
+pub use self::compare::compare;
+pub use self::critical_path::critical_path;
+pub use self::critical_path::critical_rows;
+pub use self::inferred::inferred_path;
+pub use self::inferred::InferenceScope;
+pub use self::inferred::DEFAULT_TOLERANCE;
+pub use self::inferred::MAX_TOLERANCE;
+pub use self::simulation::simulate;
+pub use self::simulation::SchedulingPolicy;
+pub use self::simulation::MAX_WORKERS;
+pub use self::trend::trend;
+pub use self::trend::DEFAULT_STAGES;
+pub use self::trend::MAX_STAGES;
+pub use self::utilization::utilization;
+pub use self::utilization::DEFAULT_BUCKETS;
+pub use self::utilization::MAX_BUCKETS;
+
+use crate::database::DatabaseResult;
+use crate::database::PostgreSQL;
+use crate::export::read_events;
+use crate::export::Nesting;
+use crate::export::Row;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+pub const DEFAULT_LONGEST: usize = 10;
+pub const MAX_LONGEST: usize = 1_000;
+
+/// Stage summary, times are milliseconds since epoch and durations are milliseconds.
+#[derive(Debug, Serialize)]
+pub struct StageStats {
+    pub targets: usize,
+    pub milestones: usize,
+    pub running: usize,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub span: i64,
+    pub task_time: i64,
+    pub durations: Option<DurationStats>,
+    pub groups: Vec<Breakdown>,
+    pub threads: Vec<Breakdown>,
+    pub longest: Vec<Row>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tasks: Option<Vec<TaskTime>>,
+}
+
+/// Total and self time of target when hierarchy is mapped. Self time excludes time covered by
+/// direct children of the target.
+#[derive(Debug, Serialize)]
+pub struct TaskTime {
+    pub name: String,
+    pub group: String,
+    pub thread: String,
+    pub depth: usize,
+    pub children: usize,
+    pub total_time: i64,
+    pub self_time: i64,
+}
+
+/// Target duration distribution, percentiles use nearest-rank method.
+#[derive(Debug, Serialize)]
+pub struct DurationStats {
+    pub min: i64,
+    pub max: i64,
+    pub mean: f64,
+    pub median: i64,
+    pub p90: i64,
+    pub p99: i64,
+}
+
+/// Totals of targets sharing the same group or thread. Busy time is the sum of target durations,
+/// percent is relative to the task time of the whole stage.
+#[derive(Debug, Serialize)]
+pub struct Breakdown {
+    pub name: String,
+    pub targets: usize,
+    pub busy_time: i64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Default)]
+struct Totals {
+    targets: usize,
+    busy_time: i64,
+}
+
+/// Accumulates statistics row by row, only durations, `longest_size` rows and targets which still
+/// may get children are kept in memory. Milestones and markers are only counted, they take no
+/// time. Time of nested targets is already covered by their top-level target, so only top-level
+/// targets are added to task time, durations and breakdowns.
+#[derive(Debug)]
+struct StatsCollector {
+    targets: usize,
+    milestones: usize,
+    running: usize,
+    start: Option<i64>,
+    end: Option<i64>,
+    task_time: i64,
+    durations: Vec<i64>,
+    groups: BTreeMap<String, Totals>,
+    threads: BTreeMap<String, Totals>,
+    longest: Vec<Row>,
+    longest_size: usize,
+    hierarchy: bool,
+    open_tasks: HashMap<String, OpenTask>,
+    task_ends: BinaryHeap<Reverse<(i64, String)>>,
+    tasks: Vec<TaskTime>,
+}
+
+/// Target with identifier which still may get children. Rows come in order of start time, so
+/// children are added in order of start time too and union of their intervals is built on the fly.
+#[derive(Debug)]
+struct OpenTask {
+    task: TaskTime,
+    start: i64,
+    end: i64,
+    covered: i64,
+    current: Option<(i64, i64)>,
+}
+
+impl OpenTask {
+    fn new(row: &Row) -> OpenTask {
+        OpenTask {
+            task: TaskTime::new(row),
+            start: row.start,
+            end: row.end,
+            covered: 0,
+            current: None,
+        }
+    }
+
+    fn add_child(&mut self, child: &Row) {
+        let start = child.start.max(self.start);
+        let end = child.end.min(self.end);
+
+        self.task.children += 1;
+
+        if start >= end {
+            return;
+        }
+
+        self.current = match self.current {
+            Some((current_start, current_end)) if start <= current_end => {
+                Some((current_start, current_end.max(end)))
+            }
+            Some((current_start, current_end)) => {
+                self.covered += current_end - current_start;
+                Some((start, end))
+            }
+            None => Some((start, end)),
+        };
+    }
+
+    fn finish(mut self) -> TaskTime {
+        let covered = self.covered + self.current.map_or(0, |(start, end)| end - start);
+
+        self.task.self_time = self.task.total_time - covered;
+        self.task
+    }
+}
+
+impl TaskTime {
+    fn new(row: &Row) -> TaskTime {
+        TaskTime {
+            name: row.name.clone(),
+            group: row.group.clone(),
+            thread: row.thread.clone(),
+            depth: row.depth,
+            children: 0,
+            total_time: row.end - row.start,
+            self_time: row.end - row.start,
+        }
+    }
+}
+
+impl StatsCollector {
+    fn new(longest_size: usize) -> StatsCollector {
+        StatsCollector {
+            targets: 0,
+            milestones: 0,
+            running: 0,
+            start: None,
+            end: None,
+            task_time: 0,
+            durations: Vec::new(),
+            groups: BTreeMap::new(),
+            threads: BTreeMap::new(),
+            longest: Vec::new(),
+            longest_size,
+            hierarchy: false,
+            open_tasks: HashMap::new(),
+            task_ends: BinaryHeap::new(),
+            tasks: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, row: &Row) {
+        if !row.is_task() {
+            self.milestones += 1;
+            return;
+        }
+
+        let duration = row.end - row.start;
+
+        self.targets += 1;
+
+        if row.running {
+            self.running += 1;
+        }
+
+        self.start = Some(self.start.map_or(row.start, |start| start.min(row.start)));
+        self.end = Some(self.end.map_or(row.end, |end| end.max(row.end)));
+
+        if row.depth == 0 {
+            self.task_time += duration;
+            self.durations.push(duration);
+
+            add_totals(&mut self.groups, &row.group, duration);
+            add_totals(&mut self.threads, &row.thread, duration);
+        }
+
+        if row.id.is_some() || row.parent.is_some() {
+            self.add_nested(row);
+        }
+
+        if self.longest_size > 0 {
+            self.longest.push(row.clone());
+
+            // Trim only when buffer doubled, so rows are sorted rarely.
+            if self.longest.len() >= 2 * self.longest_size {
+                self.trim_longest();
+            }
+        }
+    }
+
+    /// Finishes targets which ended before `row` started, they can not get more children.
+    fn add_nested(&mut self, row: &Row) {
+        self.hierarchy = true;
+        self.close_tasks(Some(row.start));
+
+        if let Some(parent) = row
+            .parent
+            .as_ref()
+            .and_then(|id| self.open_tasks.get_mut(id))
+        {
+            parent.add_child(row);
+        }
+
+        match &row.id {
+            Some(id) => {
+                self.open_tasks.insert(id.clone(), OpenTask::new(row));
+                self.task_ends.push(Reverse((row.end, id.clone())));
+            }
+            None => self.add_task(TaskTime::new(row)),
+        }
+    }
+
+    /// Finishes open targets ended at or before `time`, all of them when time is not given.
+    fn close_tasks(&mut self, time: Option<i64>) {
+        while let Some(Reverse((end, _))) = self.task_ends.peek() {
+            if time.is_some_and(|time| *end > time) {
+                break;
+            }
+
+            if let Some(Reverse((_, id))) = self.task_ends.pop() {
+                if let Some(task) = self.open_tasks.remove(&id) {
+                    self.add_task(task.finish());
+                }
+            }
+        }
+    }
+
+    fn add_task(&mut self, task: TaskTime) {
+        self.tasks.push(task);
+
+        if self.tasks.len() >= 2 * self.longest_size.max(1) {
+            self.trim_tasks();
+        }
+    }
+
+    fn trim_tasks(&mut self) {
+        self.tasks
+            .sort_by_key(|task| (Reverse(task.self_time), Reverse(task.total_time)));
+        self.tasks.truncate(self.longest_size);
+    }
+
+    fn finish(mut self) -> StageStats {
+        let span = match (self.start, self.end) {
+            (Some(start), Some(end)) => end - start,
+            _ => 0,
+        };
+        let task_time = self.task_time;
+
+        self.trim_longest();
+        self.close_tasks(None);
+        self.trim_tasks();
+        self.durations.sort_unstable();
+
+        StageStats {
+            targets: self.targets,
+            milestones: self.milestones,
+            running: self.running,
+            start: self.start,
+            end: self.end,
+            span,
+            task_time,
+            durations: duration_stats(&self.durations),
+            groups: breakdown(self.groups, task_time),
+            threads: breakdown(self.threads, task_time),
+            longest: self.longest,
+            tasks: if self.hierarchy {
+                Some(self.tasks)
+            } else {
+                None
+            },
+        }
+    }
+
+    fn trim_longest(&mut self) {
+        self.longest
+            .sort_by_key(|row| (Reverse(row.end - row.start), row.start));
+        self.longest.truncate(self.longest_size);
+    }
+}
+
+/// Computes statistics of the stage reading targets and events from database.
+pub fn collect(
+    postgres: &PostgreSQL,
+    database: &str,
+    stage: &str,
+    longest_size: usize,
+) -> DatabaseResult<StageStats> {
+    let mut collector = StatsCollector::new(longest_size);
+    let mut nesting = Nesting::new();
+
+    let mut add = |row: Row| {
+        collector.add(&row);
+
+        Ok::<(), Infallible>(())
+    };
+
+    postgres
+        .data(database, stage, |target| {
+            nesting.push(Row::from_target(target), &mut add)
+        })?
+        .unwrap_or_else(|never| match never {});
+    nesting
+        .finish(&mut add)
+        .unwrap_or_else(|never| match never {});
+
+    for row in read_events(postgres, database, stage)? {
+        collector.add(&row);
+    }
+
+    Ok(collector.finish())
+}
+
+fn add_totals(totals: &mut BTreeMap<String, Totals>, name: &str, duration: i64) {
+    let totals = totals.entry(name.into()).or_default();
+
+    totals.targets += 1;
+    totals.busy_time += duration;
+}
+
+fn breakdown(totals: BTreeMap<String, Totals>, task_time: i64) -> Vec<Breakdown> {
+    totals
+        .into_iter()
+        .map(|(name, totals)| Breakdown {
+            name,
+            targets: totals.targets,
+            busy_time: totals.busy_time,
+            percent: percent(totals.busy_time, task_time),
+        })
+        .collect()
+}
+
+fn duration_stats(sorted: &[i64]) -> Option<DurationStats> {
+    let min = *sorted.first()?;
+    let max = *sorted.last()?;
+    let sum: i64 = sorted.iter().sum();
+
+    Some(DurationStats {
+        min,
+        max,
+        mean: sum as f64 / sorted.len() as f64,
+        median: percentile(sorted, 50),
+        p90: percentile(sorted, 90),
+        p99: percentile(sorted, 99),
+    })
+}
+
+/// Nearest-rank percentile of non empty sorted slice.
+fn percentile(sorted: &[i64], percent: usize) -> i64 {
+    let rank = (percent * sorted.len()).div_ceil(100);
+
+    sorted[rank.max(1) - 1]
+}
+
+fn percent(value: i64, total: i64) -> f64 {
+    if total > 0 {
+        100.0 * value as f64 / total as f64
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_rows(rows: &[Row], longest_size: usize) -> StageStats {
+        let mut collector = StatsCollector::new(longest_size);
+
+        rows.iter().for_each(|row| collector.add(row));
+        collector.finish()
+    }
+
+    #[test]
+    fn tasks_without_hierarchy() {
+        let stats = collect_rows(&[Row::task("a", 0, 10)], 10);
+
+        assert!(stats.tasks.is_none());
+    }
+
+    #[test]
+    fn self_time_excludes_union_of_children() {
+        let rows = [
+            Row::task("build", 0, 100).id("1"),
+            Row::task("compile", 10, 40).id("2").parent("1").depth(1),
+            Row::task("link", 30, 50).id("3").parent("1").depth(1),
+            Row::task("test", 90, 120).parent("1").depth(1),
+            Row::task("deploy", 200, 210).id("4"),
+        ];
+        let stats = collect_rows(&rows, 10);
+        let tasks = stats.tasks.unwrap();
+        let build = tasks.iter().find(|task| task.name == "build").unwrap();
+
+        assert_eq!(tasks.len(), 5);
+        assert_eq!(build.children, 3);
+        assert_eq!(build.total_time, 100);
+        assert_eq!(build.self_time, 50);
+        assert_eq!(tasks[0].name, "build");
+        assert_eq!(stats.task_time, 110);
+    }
+
+    #[test]
+    fn tasks_are_limited_to_longest_self_time() {
+        let rows: Vec<Row> = (0..10)
+            .map(|index| {
+                let id = index.to_string();
+
+                Row::task("a", index * 100, index * 100 + index).id(&id)
+            })
+            .collect();
+        let tasks = collect_rows(&rows, 3).tasks.unwrap();
+        let self_times: Vec<i64> = tasks.iter().map(|task| task.self_time).collect();
+
+        assert_eq!(self_times, vec![9, 8, 7]);
+    }
+
+    #[test]
+    fn empty_stage() {
+        let stats = collect_rows(&[], 10);
+
+        assert_eq!(stats.targets, 0);
+        assert_eq!(stats.start, None);
+        assert_eq!(stats.span, 0);
+        assert!(stats.durations.is_none());
+        assert!(stats.groups.is_empty());
+        assert!(stats.longest.is_empty());
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let sorted: Vec<i64> = (1..=10).collect();
+        let durations = duration_stats(&sorted).unwrap();
+
+        assert_eq!(durations.min, 1);
+        assert_eq!(durations.max, 10);
+        assert_eq!(durations.mean, 5.5);
+        assert_eq!(durations.median, 5);
+        assert_eq!(durations.p90, 9);
+        assert_eq!(durations.p99, 10);
+        assert_eq!(percentile(&[7], 50), 7);
+        assert_eq!(percentile(&[7], 99), 7);
+        assert_eq!(percentile(&[1, 2, 3], 0), 1);
+    }
+
+    #[test]
+    fn milestones_and_zero_length_targets() {
+        let rows = [
+            Row::task("a", 0, 10),
+            Row::milestone("deploy", 10),
+            Row::task("b", 20, 20),
+        ];
+        let stats = collect_rows(&rows, 10);
+
+        assert_eq!(stats.targets, 2);
+        assert_eq!(stats.milestones, 1);
+        assert_eq!(stats.span, 20);
+        assert_eq!(stats.task_time, 10);
+        assert_eq!(stats.durations.unwrap().min, 0);
+    }
+
+    #[test]
+    fn breakdown_and_longest_ties() {
+        let rows = [
+            Row::task("a", 0, 30).group("compile"),
+            Row::task("b", 0, 10).group("test"),
+            Row::task("c", 10, 20).group("test"),
+        ];
+        let stats = collect_rows(&rows, 2);
+        let longest: Vec<&str> = stats.longest.iter().map(|row| row.name.as_str()).collect();
+
+        assert_eq!(longest, ["a", "b"]);
+        assert_eq!(stats.groups.len(), 2);
+        assert_eq!(stats.groups[0].name, "compile");
+        assert_eq!(stats.groups[1].targets, 2);
+        assert_eq!(stats.groups[1].busy_time, 20);
+        assert!((stats.groups[1].percent - 40.0).abs() < 1e-9);
+    }
+}
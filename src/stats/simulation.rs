@@ -0,0 +1,307 @@
+use super::critical_path::Graph;
+use crate::database::DatabaseResult;
+use crate::database::PostgreSQL;
+use crate::export;
+use crate::export::Row;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::collections::BinaryHeap;
+use std::str::FromStr;
+
+pub const MAX_WORKERS: usize = 10_000;
+
+/// Order in which ready targets are given to free workers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchedulingPolicy {
+    #[default]
+    Original,
+    Longest,
+}
+
+impl FromStr for SchedulingPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<SchedulingPolicy, String> {
+        match value {
+            "original" => Ok(SchedulingPolicy::Original),
+            "longest" => Ok(SchedulingPolicy::Longest),
+            _ => Err(format!(
+                "Unknown scheduling policy `{}`, expected original or longest",
+                value
+            )),
+        }
+    }
+}
+
+/// Result of replaying stage targets on `workers` simulated threads. `makespan` is the simulated
+/// wall-clock time, `span` is the actual one. Rows of the synthetic timeline start at the actual
+/// stage start and threads are named by worker numbers.
+#[derive(Debug, Serialize)]
+pub struct Simulation {
+    pub workers: usize,
+    pub policy: SchedulingPolicy,
+    pub span: i64,
+    pub makespan: i64,
+    pub speedup: f64,
+    pub dependencies: usize,
+    pub unscheduled: Vec<String>,
+    pub rows: Vec<Row>,
+}
+
+/// Reads targets of the stage, and its dependencies when they are mapped, and replays them with
+/// list scheduler. Nested targets are replayed as part of their top-level target.
+pub fn simulate(
+    postgres: &PostgreSQL,
+    database: &str,
+    stage: &str,
+    workers: usize,
+    policy: SchedulingPolicy,
+) -> DatabaseResult<Simulation> {
+    let mut rows = export::read_rows(postgres, database, stage)?;
+
+    rows.retain(|row| row.depth == 0);
+
+    let dependencies = if postgres.has_dependencies() {
+        postgres.dependencies(database, stage)?
+    } else {
+        Vec::new()
+    };
+    let graph = Graph::new(&rows, &dependencies);
+
+    Ok(replay(&rows, &graph, workers, policy))
+}
+
+/// Every time a worker is free it takes the first ready target, a target is ready when all its
+/// prerequisites are finished. Without dependencies all targets are ready at the start.
+fn replay(rows: &[Row], graph: &Graph, workers: usize, policy: SchedulingPolicy) -> Simulation {
+    let durations: Vec<i64> = rows.iter().map(|row| row.end - row.start).collect();
+    let priority = |index: usize| match policy {
+        SchedulingPolicy::Original => Reverse((rows[index].start, 0, index)),
+        SchedulingPolicy::Longest => Reverse((-durations[index], rows[index].start, index)),
+    };
+    let mut incoming: Vec<usize> = graph.predecessors.iter().map(Vec::len).collect();
+    let mut ready: BinaryHeap<_> = (0..rows.len())
+        .filter(|&index| incoming[index] == 0)
+        .map(priority)
+        .collect();
+    let mut free: BinaryHeap<_> = (0..workers).map(Reverse).collect();
+    let mut busy = BinaryHeap::new();
+    let mut scheduled = vec![None; rows.len()];
+    let mut time = 0;
+
+    loop {
+        while let Some(&Reverse(worker)) = free.peek() {
+            let Reverse((_, _, index)) = match ready.pop() {
+                Some(next) => next,
+                None => break,
+            };
+
+            free.pop();
+            scheduled[index] = Some((time, worker));
+            busy.push(Reverse((time + durations[index], worker, index)));
+        }
+
+        let Reverse((end, _, _)) = match busy.peek() {
+            Some(&next) => next,
+            None => break,
+        };
+
+        time = end;
+
+        while let Some(&Reverse((end, worker, index))) = busy.peek() {
+            if end != time {
+                break;
+            }
+
+            busy.pop();
+            free.push(Reverse(worker));
+
+            for &successor in &graph.successors[index] {
+                incoming[successor] -= 1;
+
+                if incoming[successor] == 0 {
+                    ready.push(priority(successor));
+                }
+            }
+        }
+    }
+
+    let stage_start = rows.iter().map(|row| row.start).min().unwrap_or(0);
+    let stage_end = rows.iter().map(|row| row.end).max().unwrap_or(0);
+    let span = stage_end - stage_start;
+    let worker_width = workers.to_string().len();
+    let mut simulated: Vec<Row> = scheduled
+        .iter()
+        .enumerate()
+        .filter_map(|(index, scheduled)| scheduled.map(|schedule| (index, schedule)))
+        .map(|(index, (start, worker))| Row {
+            start: stage_start + start,
+            end: stage_start + start + durations[index],
+            thread: format!("worker {:0width$}", worker + 1, width = worker_width),
+            critical: false,
+            ..rows[index].clone()
+        })
+        .collect();
+    let unscheduled: BTreeSet<_> = scheduled
+        .iter()
+        .enumerate()
+        .filter(|(_, scheduled)| scheduled.is_none())
+        .map(|(index, _)| rows[index].name.clone())
+        .collect();
+
+    simulated.sort_by_key(|row| row.start);
+
+    let makespan = simulated
+        .iter()
+        .map(|row| row.end - stage_start)
+        .max()
+        .unwrap_or(0);
+
+    Simulation {
+        workers,
+        policy,
+        span,
+        makespan,
+        speedup: if makespan > 0 {
+            span as f64 / makespan as f64
+        } else {
+            0.0
+        },
+        dependencies: graph.edges,
+        unscheduled: unscheduled.into_iter().collect(),
+        rows: simulated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Dependency;
+
+    fn simulate_rows(
+        rows: &[Row],
+        dependencies: &[Dependency],
+        workers: usize,
+        policy: SchedulingPolicy,
+    ) -> Simulation {
+        replay(rows, &Graph::new(rows, dependencies), workers, policy)
+    }
+
+    fn schedule(simulation: &Simulation) -> Vec<(&str, i64, &str)> {
+        simulation
+            .rows
+            .iter()
+            .map(|row| (row.name.as_str(), row.start, row.thread.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn empty_stage() {
+        let simulation = simulate_rows(&[], &[], 4, SchedulingPolicy::Original);
+
+        assert_eq!(simulation.makespan, 0);
+        assert_eq!(simulation.speedup, 0.0);
+        assert!(simulation.rows.is_empty());
+    }
+
+    #[test]
+    fn one_worker_runs_targets_in_original_order() {
+        let rows = [
+            Row::task("b", 10, 40),
+            Row::task("a", 0, 10),
+            Row::task("c", 20, 30),
+        ];
+        let simulation = simulate_rows(&rows, &[], 1, SchedulingPolicy::Original);
+
+        assert_eq!(
+            schedule(&simulation),
+            [
+                ("a", 0, "worker 1"),
+                ("b", 10, "worker 1"),
+                ("c", 40, "worker 1")
+            ]
+        );
+        assert_eq!(simulation.span, 40);
+        assert_eq!(simulation.makespan, 50);
+    }
+
+    #[test]
+    fn longest_first_shortens_makespan() {
+        let rows = [
+            Row::task("a", 0, 10),
+            Row::task("b", 0, 10),
+            Row::task("c", 10, 30),
+            Row::task("d", 10, 50),
+        ];
+        let original = simulate_rows(&rows, &[], 2, SchedulingPolicy::Original);
+        let longest = simulate_rows(&rows, &[], 2, SchedulingPolicy::Longest);
+
+        assert_eq!(original.makespan, 50);
+        assert_eq!(longest.makespan, 40);
+        assert!(longest
+            .rows
+            .iter()
+            .any(|row| row.name == "d" && row.start == 0));
+    }
+
+    #[test]
+    fn prerequisites_finish_before_targets_start() {
+        let rows = [
+            Row::task("link", 0, 10),
+            Row::task("compile", 0, 30),
+            Row::task("docs", 0, 5),
+        ];
+        let dependencies = [Dependency::new("link", "compile")];
+        let simulation = simulate_rows(&rows, &dependencies, 4, SchedulingPolicy::Original);
+
+        assert_eq!(
+            schedule(&simulation),
+            [
+                ("compile", 0, "worker 1"),
+                ("docs", 0, "worker 2"),
+                ("link", 30, "worker 1"),
+            ]
+        );
+        assert_eq!(simulation.dependencies, 1);
+        assert_eq!(simulation.makespan, 40);
+    }
+
+    #[test]
+    fn cycles_are_unscheduled() {
+        let rows = [
+            Row::task("a", 0, 10),
+            Row::task("b", 10, 20),
+            Row::task("c", 0, 5),
+        ];
+        let dependencies = [Dependency::new("a", "b"), Dependency::new("b", "a")];
+        let simulation = simulate_rows(&rows, &dependencies, 2, SchedulingPolicy::Original);
+
+        assert_eq!(simulation.unscheduled, ["a", "b"]);
+        assert_eq!(schedule(&simulation), [("c", 0, "worker 1")]);
+    }
+
+    #[test]
+    fn zero_length_targets_release_workers_at_once() {
+        let rows = [
+            Row::task("a", 0, 0),
+            Row::task("b", 0, 0),
+            Row::task("c", 0, 10),
+        ];
+        let dependencies = [Dependency::new("c", "a")];
+        let simulation = simulate_rows(&rows, &dependencies, 1, SchedulingPolicy::Original);
+
+        assert_eq!(simulation.makespan, 10);
+        assert_eq!(simulation.rows.len(), 3);
+        assert!(simulation.unscheduled.is_empty());
+    }
+
+    #[test]
+    fn worker_names_are_padded() {
+        let rows = [Row::task("a", 0, 10)];
+        let simulation = simulate_rows(&rows, &[], 12, SchedulingPolicy::Original);
+
+        assert_eq!(simulation.rows[0].thread, "worker 01");
+        assert_eq!(simulation.speedup, 1.0);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::database::DatabaseResult;
+use crate::database::PostgreSQL;
+use crate::database::QualityReport;
+use crate::database::TrendTarget;
+use crate::export;
+use crate::export::Row;
+
+pub const DEFAULT_STAGES: i64 = 20;
+pub const MAX_STAGES: i64 = 1_000;
+
+/// Matching targets of the last stages ordered by stage start time with data quality report of
+/// read targets.
+#[derive(Debug, Serialize)]
+pub struct Trend {
+    pub stages: Vec<StageTrend>,
+    pub quality: QualityReport,
+}
+
+/// Matching targets of one stage, `duration` is the sum of their durations.
+#[derive(Debug, Serialize)]
+pub struct StageTrend {
+    pub stage: String,
+    pub start: i64,
+    pub targets: usize,
+    pub duration: i64,
+    pub points: Vec<TrendPoint>,
+}
+
+/// Single target, `offset` is relative to the start of its stage.
+#[derive(Debug, Serialize)]
+pub struct TrendPoint {
+    pub name: String,
+    pub group: String,
+    pub thread: String,
+    pub start: i64,
+    pub offset: i64,
+    pub duration: i64,
+    pub running: bool,
+}
+
+/// Reads targets matching name pattern and group from the last `stages` stages.
+pub fn trend(
+    postgres: &PostgreSQL,
+    database: &str,
+    name_pattern: Option<&str>,
+    group: Option<&str>,
+    stages: i64,
+) -> DatabaseResult<Trend> {
+    let (targets, quality) = postgres.trend(database, name_pattern, group, stages)?;
+
+    Ok(Trend {
+        stages: group_stages(targets),
+        quality,
+    })
+}
+
+/// Groups targets ordered by stage into stages, targets of one stage must be adjacent.
+fn group_stages(targets: Vec<TrendTarget>) -> Vec<StageTrend> {
+    let mut result: Vec<StageTrend> = Vec::new();
+
+    for trend_target in targets {
+        let row = Row::from_target(&trend_target.target);
+        let stage_start = export::to_millis(trend_target.stage_start);
+        let point = TrendPoint {
+            offset: row.start - stage_start,
+            duration: row.end - row.start,
+            start: row.start,
+            name: row.name,
+            group: row.group,
+            thread: row.thread,
+            running: row.running,
+        };
+
+        match result.last_mut() {
+            Some(stage) if stage.stage == trend_target.stage => {
+                stage.targets += 1;
+                stage.duration += point.duration;
+                stage.points.push(point);
+            }
+            _ => result.push(StageTrend {
+                stage: trend_target.stage,
+                start: stage_start,
+                targets: 1,
+                duration: point.duration,
+                points: vec![point],
+            }),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Target;
+    use time::Timespec;
+
+    fn trend_target(
+        stage: &str,
+        stage_start: i64,
+        name: &str,
+        start: i64,
+        end: i64,
+    ) -> TrendTarget {
+        let millis = |value: i64| Timespec::new(value / 1_000, (value % 1_000) as i32 * 1_000_000);
+
+        TrendTarget {
+            stage: stage.into(),
+            stage_start: millis(stage_start),
+            target: Target {
+                name: name.into(),
+                start_time: millis(start),
+                end_time: millis(end),
+                group: "compile".into(),
+                thread: "t1".into(),
+                running: false,
+                id: None,
+                parent: None,
+            },
+        }
+    }
+
+    #[test]
+    fn adjacent_targets_form_stage() {
+        let stages = group_stages(vec![
+            trend_target("s1", 1_000, "a", 1_000, 1_500),
+            trend_target("s1", 1_000, "a", 2_000, 2_250),
+            trend_target("s2", 5_000, "a", 5_100, 5_200),
+        ]);
+
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].stage, "s1");
+        assert_eq!(stages[0].targets, 2);
+        assert_eq!(stages[0].duration, 750);
+        assert_eq!(stages[1].stage, "s2");
+        assert_eq!(stages[1].start, 5_000);
+        assert_eq!(stages[1].targets, 1);
+        assert_eq!(stages[1].duration, 100);
+    }
+
+    #[test]
+    fn offsets_are_relative_to_stage_start() {
+        let stages = group_stages(vec![
+            trend_target("s1", 1_000, "a", 1_000, 1_500),
+            trend_target("s1", 1_000, "b", 3_250, 4_000),
+        ]);
+        let offsets: Vec<(i64, i64, i64)> = stages[0]
+            .points
+            .iter()
+            .map(|point| (point.start, point.offset, point.duration))
+            .collect();
+
+        assert_eq!(offsets, vec![(1_000, 0, 500), (3_250, 2_250, 750)]);
+    }
+
+    #[test]
+    fn no_targets_no_stages() {
+        assert!(group_stages(Vec::new()).is_empty());
+    }
+}
@@ -0,0 +1,332 @@
+use super::percent;
+use crate::database::DatabaseResult;
+use crate::database::PostgreSQL;
+use crate::export;
+use crate::export::Row;
+use std::collections::BTreeMap;
+
+pub const DEFAULT_BUCKETS: usize = 100;
+pub const MAX_BUCKETS: usize = 10_000;
+
+/// Busy and idle time of every thread within stage time span, times are milliseconds since epoch.
+#[derive(Debug, Serialize)]
+pub struct Utilization {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub span: i64,
+    pub threads: Vec<ThreadUtilization>,
+    pub concurrency: Concurrency,
+}
+
+/// Busy time is the length of union of thread targets, so overlapping targets are counted once.
+#[derive(Debug, Serialize)]
+pub struct ThreadUtilization {
+    pub name: String,
+    pub targets: usize,
+    pub busy_time: i64,
+    pub idle_time: i64,
+    pub utilization: f64,
+    pub gaps: Vec<Gap>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Gap {
+    pub start: i64,
+    pub end: i64,
+    pub length: i64,
+}
+
+/// Number of targets running at the same time. Every bucket contains time weighted average and
+/// maximum number of running targets.
+#[derive(Debug, Serialize)]
+pub struct Concurrency {
+    pub peak: usize,
+    pub buckets: Vec<Bucket>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Bucket {
+    pub start: i64,
+    pub end: i64,
+    pub average: f64,
+    pub max: usize,
+}
+
+/// Computes utilization of the stage reading targets from database. Gaps shorter than `min_gap`
+/// are not listed, but still counted as idle time.
+pub fn utilization(
+    postgres: &PostgreSQL,
+    database: &str,
+    stage: &str,
+    buckets: usize,
+    min_gap: i64,
+) -> DatabaseResult<Utilization> {
+    let mut rows = export::read_rows(postgres, database, stage)?;
+
+    // Milestones take no time, so only tasks are counted.
+    rows.retain(Row::is_task);
+
+    Ok(compute(&rows, buckets, min_gap))
+}
+
+fn compute(rows: &[Row], buckets: usize, min_gap: i64) -> Utilization {
+    let start = rows.iter().map(|row| row.start).min();
+    let end = rows.iter().map(|row| row.end).max();
+    let (threads, concurrency) = match (start, end) {
+        (Some(start), Some(end)) => (
+            thread_utilization(rows, start, end, min_gap),
+            concurrency(rows, start, end, buckets),
+        ),
+        _ => (
+            Vec::new(),
+            Concurrency {
+                peak: 0,
+                buckets: Vec::new(),
+            },
+        ),
+    };
+
+    Utilization {
+        start,
+        end,
+        span: end.unwrap_or(0) - start.unwrap_or(0),
+        threads,
+        concurrency,
+    }
+}
+
+fn thread_utilization(rows: &[Row], start: i64, end: i64, min_gap: i64) -> Vec<ThreadUtilization> {
+    let mut threads: BTreeMap<&str, Vec<(i64, i64)>> = BTreeMap::new();
+
+    for row in rows {
+        threads
+            .entry(row.thread.as_str())
+            .or_default()
+            .push((row.start, row.end));
+    }
+
+    threads
+        .into_iter()
+        .map(|(name, mut intervals)| {
+            let targets = intervals.len();
+            let mut busy_time = 0;
+            let mut gaps = Vec::new();
+            let mut position = start;
+
+            intervals.sort_unstable();
+
+            for (interval_start, interval_end) in intervals {
+                if interval_start > position {
+                    add_gap(&mut gaps, position, interval_start, min_gap);
+                    position = interval_start;
+                }
+
+                if interval_end > position {
+                    busy_time += interval_end - position;
+                    position = interval_end;
+                }
+            }
+
+            add_gap(&mut gaps, position, end, min_gap);
+
+            ThreadUtilization {
+                name: name.into(),
+                targets,
+                busy_time,
+                idle_time: (end - start) - busy_time,
+                utilization: percent(busy_time, end - start),
+                gaps,
+            }
+        })
+        .collect()
+}
+
+fn add_gap(gaps: &mut Vec<Gap>, start: i64, end: i64, min_gap: i64) {
+    let length = end - start;
+
+    if length > 0 && length >= min_gap {
+        gaps.push(Gap { start, end, length });
+    }
+}
+
+/// Sweeps over start and end events, every segment with constant number of running targets is
+/// distributed over buckets it covers. Zero-length targets never run, nested targets run within
+/// their top-level target and are not counted.
+fn concurrency(rows: &[Row], start: i64, end: i64, n_buckets: usize) -> Concurrency {
+    let mut events: Vec<(i64, i64)> = Vec::with_capacity(2 * rows.len());
+
+    for row in rows
+        .iter()
+        .filter(|row| row.depth == 0 && row.start < row.end)
+    {
+        events.push((row.start, 1));
+        events.push((row.end, -1));
+    }
+
+    // Ends are sorted before starts of the same time, so adjacent targets do not overlap.
+    events.sort_unstable();
+
+    let span = (end - start).max(1);
+    let n_buckets = n_buckets.max(1).min(span as usize);
+    let bucket_bound =
+        |index: usize| start + (span as i128 * index as i128 / n_buckets as i128) as i64;
+    let mut sums = vec![0i64; n_buckets];
+    let mut maximums = vec![0usize; n_buckets];
+    let mut running: i64 = 0;
+    let mut peak = 0;
+    let mut position = start;
+    let mut bucket = 0;
+
+    for (time, delta) in events {
+        while position < time && bucket < n_buckets {
+            let bucket_end = bucket_bound(bucket + 1);
+            let segment_end = time.min(bucket_end);
+
+            if running > 0 {
+                sums[bucket] += running * (segment_end - position);
+                maximums[bucket] = maximums[bucket].max(running as usize);
+            }
+
+            position = segment_end;
+
+            if position >= bucket_end {
+                bucket += 1;
+            }
+        }
+
+        running = (running + delta).max(0);
+        peak = peak.max(running as usize);
+
+        if running > 0 && bucket < n_buckets {
+            maximums[bucket] = maximums[bucket].max(running as usize);
+        }
+    }
+
+    let buckets = (0..n_buckets)
+        .map(|index| {
+            let bucket_start = bucket_bound(index);
+            let bucket_end = bucket_bound(index + 1);
+
+            Bucket {
+                start: bucket_start,
+                end: bucket_end,
+                average: sums[index] as f64 / (bucket_end - bucket_start).max(1) as f64,
+                max: maximums[index],
+            }
+        })
+        .collect();
+
+    Concurrency { peak, buckets }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_stage() {
+        let utilization = compute(&[], DEFAULT_BUCKETS, 0);
+
+        assert_eq!(utilization.span, 0);
+        assert!(utilization.threads.is_empty());
+        assert_eq!(utilization.concurrency.peak, 0);
+        assert!(utilization.concurrency.buckets.is_empty());
+    }
+
+    #[test]
+    fn zero_length_rows_are_not_running() {
+        let rows = [
+            Row::task("", 0, 10).thread("a"),
+            Row::task("", 20, 20).thread("a"),
+            Row::task("", 20, 20).thread("b"),
+            Row::task("", 30, 40).thread("b"),
+        ];
+        let concurrency = compute(&rows, 4, 0).concurrency;
+
+        assert_eq!(concurrency.peak, 1);
+        assert!(concurrency.buckets.iter().all(|bucket| bucket.max <= 1));
+    }
+
+    #[test]
+    fn adjacent_targets_do_not_overlap() {
+        let rows = [
+            Row::task("", 0, 10).thread("a"),
+            Row::task("", 10, 20).thread("b"),
+            Row::task("", 5, 15).thread("c"),
+        ];
+        let concurrency = compute(&rows, 2, 0).concurrency;
+
+        assert_eq!(concurrency.peak, 2);
+        assert_eq!(concurrency.buckets.len(), 2);
+        assert_eq!(concurrency.buckets[0].average, 1.5);
+        assert_eq!(concurrency.buckets[1].average, 1.5);
+    }
+
+    #[test]
+    fn busy_time_is_union_and_gaps_are_listed() {
+        let rows = [
+            Row::task("", 0, 10).thread("a"),
+            Row::task("", 5, 15).thread("a"),
+            Row::task("", 30, 40).thread("a"),
+            Row::task("", 0, 40).thread("b"),
+        ];
+        let threads = compute(&rows, 1, 10).threads;
+
+        assert_eq!(threads[0].name, "a");
+        assert_eq!(threads[0].targets, 3);
+        assert_eq!(threads[0].busy_time, 25);
+        assert_eq!(threads[0].idle_time, 15);
+        assert_eq!(threads[0].gaps.len(), 1);
+        assert_eq!(threads[0].gaps[0].start, 15);
+        assert_eq!(threads[0].gaps[0].length, 15);
+        assert_eq!(threads[1].utilization, 100.0);
+    }
+
+    #[test]
+    fn buckets_cover_the_span() {
+        let concurrency = compute(&[Row::task("", 0, 10).thread("a")], 3, 0).concurrency;
+        let bounds: Vec<(i64, i64)> = concurrency
+            .buckets
+            .iter()
+            .map(|bucket| (bucket.start, bucket.end))
+            .collect();
+
+        assert_eq!(bounds, [(0, 3), (3, 6), (6, 10)]);
+        assert!(concurrency
+            .buckets
+            .iter()
+            .all(|bucket| bucket.average == 1.0 && bucket.max == 1));
+    }
+
+    #[test]
+    fn buckets_are_at_least_one_millisecond() {
+        let concurrency =
+            compute(&[Row::task("", 0, 5).thread("a")], DEFAULT_BUCKETS, 0).concurrency;
+
+        assert_eq!(concurrency.buckets.len(), 5);
+    }
+
+    #[test]
+    fn bucket_average_is_time_weighted() {
+        let rows = [
+            Row::task("", 0, 4).thread("a"),
+            Row::task("", 2, 6).thread("b"),
+        ];
+        let concurrency = compute(&rows, 2, 0).concurrency;
+
+        assert_eq!(concurrency.peak, 2);
+        assert!((concurrency.buckets[0].average - 4.0 / 3.0).abs() < 1e-9);
+        assert!((concurrency.buckets[1].average - 4.0 / 3.0).abs() < 1e-9);
+        assert_eq!(concurrency.buckets[0].max, 2);
+        assert_eq!(concurrency.buckets[1].max, 2);
+    }
+
+    #[test]
+    fn nested_rows_are_not_counted() {
+        let nested = Row::task("", 2, 8).thread("a").parent("1").depth(1);
+        let concurrency = compute(&[Row::task("", 0, 10).thread("a"), nested], 1, 0).concurrency;
+
+        assert_eq!(concurrency.peak, 1);
+        assert_eq!(concurrency.buckets[0].average, 1.0);
+    }
+}